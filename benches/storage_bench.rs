@@ -0,0 +1,99 @@
+//! Compares iterating and accessing the different component storages.
+//!
+//! Run with `cargo bench --features nightly`.
+
+#![cfg_attr(feature="nightly", feature(test))]
+
+#[cfg(feature="nightly")]
+#[macro_use]
+extern crate ecs;
+#[cfg(feature="nightly")]
+extern crate test;
+
+#[cfg(feature="nightly")]
+mod benches
+{
+    use ecs::{Join, World};
+    use test::{black_box, Bencher};
+
+    components! {
+        #[builder(Parts)]
+        struct Stored {
+            #[hot] hot: u64,
+            #[cold] cold: u64,
+            #[dense] dense: u64,
+            #[hot] filler: (),
+        }
+    }
+
+    systems! {
+        struct NoSystems<Stored, ()>;
+    }
+
+    // 10000 entities, of which only every tenth has the benchmarked components.
+    fn sparse_world() -> World<NoSystems>
+    {
+        let mut world = World::<NoSystems>::new();
+        for i in 0..10000
+        {
+            if i % 10 == 0
+            {
+                world.create_entity(Parts { hot: Some(i), cold: Some(i), dense: Some(i), ..Default::default() });
+            }
+            else
+            {
+                world.create_entity(Parts { filler: Some(()), ..Default::default() });
+            }
+        }
+        world
+    }
+
+    #[bench]
+    fn iter_hot(b: &mut Bencher)
+    {
+        let world = sparse_world();
+        b.iter(|| black_box((&world.hot,).join().map(|(&x,)| x).sum::<u64>()));
+    }
+
+    #[bench]
+    fn iter_cold(b: &mut Bencher)
+    {
+        let world = sparse_world();
+        b.iter(|| black_box((&world.cold,).join().map(|(&x,)| x).sum::<u64>()));
+    }
+
+    #[bench]
+    fn iter_dense(b: &mut Bencher)
+    {
+        let world = sparse_world();
+        b.iter(|| black_box((&world.dense,).join().map(|(&x,)| x).sum::<u64>()));
+    }
+
+    #[bench]
+    fn iter_dense_slice(b: &mut Bencher)
+    {
+        let world = sparse_world();
+        b.iter(|| black_box(world.dense.as_slice().iter().sum::<u64>()));
+    }
+
+    #[bench]
+    fn lookup_hot(b: &mut Bencher)
+    {
+        let world = sparse_world();
+        b.iter(|| black_box(world.entities().filter_map(|e| world.hot.get(&e)).sum::<u64>()));
+    }
+
+    #[bench]
+    fn lookup_cold(b: &mut Bencher)
+    {
+        let world = sparse_world();
+        b.iter(|| black_box(world.entities().filter_map(|e| world.cold.get(&e)).sum::<u64>()));
+    }
+
+    #[bench]
+    fn lookup_dense(b: &mut Bencher)
+    {
+        let world = sparse_world();
+        b.iter(|| black_box(world.entities().filter_map(|e| world.dense.get(&e)).sum::<u64>()));
+    }
+}
@@ -0,0 +1,86 @@
+//! Changes to the world that are recorded now and applied during the next flush.
+
+use std::mem;
+
+use {BuildData, EntityBuilder, ModifyData};
+use {Component, ComponentList, ComponentManager};
+use Entity;
+use Storage;
+
+#[doc(hidden)]
+pub enum Command<C: ComponentManager>
+{
+    Create(Box<FnOnce(BuildData<C>, &mut C) + Send>),
+    Remove(Entity),
+    Modify(Entity, Box<FnOnce(ModifyData<C>, &mut C) + Send>),
+}
+
+/// A queue of changes to entities, available to systems through `DataHelper::commands()`.
+///
+/// The commands are applied in the order they were recorded when the world is next flushed,
+/// and systems are notified of each change before the next command runs. Commands that refer
+/// to an entity that no longer exists are skipped.
+pub struct Commands<C: ComponentManager>
+{
+    queue: Vec<Command<C>>,
+}
+
+impl<C: ComponentManager> Commands<C>
+{
+    pub fn new() -> Commands<C>
+    {
+        Commands
+        {
+            queue: Vec::new(),
+        }
+    }
+
+    /// Creates an entity with the given builder, such as a closure or a `#[builder]` struct.
+    ///
+    /// The entity only gets its id when the command runs, so anything that needs it has to be
+    /// done by the builder.
+    pub fn create_entity<B>(&mut self, builder: B) where B: EntityBuilder<C> + Send + 'static
+    {
+        self.queue.push(Command::Create(Box::new(move |e: BuildData<C>, c: &mut C| builder.build(e, c))));
+    }
+
+    /// Removes an entity.
+    pub fn remove_entity(&mut self, entity: Entity)
+    {
+        self.queue.push(Command::Remove(entity));
+    }
+
+    /// Adds a component to an entity, or replaces its existing one.
+    ///
+    /// eg: `data.commands().insert(entity, |c| &mut c.position, Position { x: 0.0, y: 0.0 })`
+    pub fn insert<T, S, F>(&mut self, entity: Entity, list: F, component: T)
+        where T: Component + Send, S: Storage<T>, F: FnOnce(&mut C) -> &mut ComponentList<C, T, S> + Send + 'static
+    {
+        self.modify_entity(entity, move |e, c| { list(c).insert(&e, component); });
+    }
+
+    /// Removes a component from an entity.
+    pub fn remove<T, S, F>(&mut self, entity: Entity, list: F)
+        where T: Component, S: Storage<T>, F: FnOnce(&mut C) -> &mut ComponentList<C, T, S> + Send + 'static
+    {
+        self.modify_entity(entity, move |e, c| { list(c).remove(&e); });
+    }
+
+    /// Modifies an entity like `World::modify_entity`.
+    pub fn modify_entity<F>(&mut self, entity: Entity, modifier: F) where F: FnOnce(ModifyData<C>, &mut C) + Send + 'static
+    {
+        self.queue.push(Command::Modify(entity, Box::new(modifier)));
+    }
+
+    /// Returns true if no commands are waiting to be applied.
+    pub fn is_empty(&self) -> bool
+    {
+        self.queue.is_empty()
+    }
+
+    #[doc(hidden)]
+    pub fn __take(&mut self) -> Vec<Command<C>>
+    {
+        mem::replace(&mut self.queue, Vec::new())
+    }
+}
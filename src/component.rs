@@ -1,196 +1,670 @@
 
 #[cfg(feature="serialisation")] use cereal::{CerealData, CerealError, CerealResult};
+#[cfg(feature="serde")] use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature="serde")] use serde::ser::SerializeSeq;
 #[cfg(feature="serialisation")] use std::io::{Read, Write};
 
-use std::collections::HashMap;
+use std::cmp;
 use std::marker::PhantomData;
 use std::ops::{Index, IndexMut};
 use vec_map::VecMap;
 
-use self::InnerComponentList::{Hot, Cold};
-
 use {BuildData, EditData, ModifyData};
-use {IndexedEntity};
+use {EcsError, EcsResult};
+use {Entity, EntityData, IndexedEntity};
 use ComponentManager;
+use join::Joinable;
+use map::{EntityMap, MapEntities, __Merge};
+use storage::{Storage, HotStorage, ColdStorage, DenseStorage};
 
 pub trait Component: 'static {}
 
 impl<T:'static> Component for T {}
 
-pub struct ComponentList<C: ComponentManager, T: Component>(InnerComponentList<T>, PhantomData<C>);
+/// The components of one type, stored in `S`.
+pub struct ComponentList<C: ComponentManager, T: Component, S: Storage<T> = HotStorage<T>>(S, Changes<C>, PhantomData<T>);
+
+/// A value of a component list's change clock.
+pub type Tick = u64;
+
+/// Records when the components of a list were added, changed or removed.
+///
+/// Each list has its own clock, which advances whenever a component is inserted, mutably
+/// accessed or removed. A system that remembers `tick()` after processing can later ask
+/// for everything that happened since then.
+///
+/// Removals are kept until the second `World::update` after they happened, so every active
+/// system gets to see them. Tracking starts afresh when a world is loaded.
+pub struct Changes<C: ComponentManager>
+{
+    tick: Tick,
+    stamps: VecMap<Stamp<C>>,
+    removed: Vec<(Entity, Tick)>,
+    horizon: Tick,
+}
 
-enum InnerComponentList<T: Component>
+struct Stamp<C: ComponentManager>
 {
-    Hot(VecMap<T>),
-    Cold(HashMap<usize, T>),
+    entity: IndexedEntity<C>,
+    added: Tick,
+    changed: Tick,
 }
 
 #[cfg(feature="serialisation")]
-unsafe impl<C: ComponentManager, T: Component> CerealData for ComponentList<C, T> where T: CerealData {
+unsafe impl<C: ComponentManager, T: Component, S: Storage<T>> CerealData for ComponentList<C, T, S> where T: CerealData {
     fn write(&self, w: &mut Write) -> CerealResult<()> {
-        self.0.write(w)
+        // Older saves recorded the list kind (Hot = 1, Cold = 2). Both are laid out the same,
+        // so any storage can read either.
+        try!(1u8.write(w));
+        try!((self.0.len() as u64).write(w));
+        for (idx, data) in self.0.iter() {
+            try!((idx as u64).write(w));
+            try!(data.write(w));
+        }
+        Ok(())
     }
 
     fn read(r: &mut Read) -> CerealResult<Self> {
-        CerealData::read(r).map(|inner| ComponentList(inner, PhantomData))
+        let kind: u8 = try!(CerealData::read(r));
+        if kind != 1 && kind != 2 {
+            return Err(CerealError::Msg(format!("Unrecognized list type (Hot = 1, Cold = 2, Found {:?})", kind)));
+        }
+        let len = try!(u64::read(r)) as usize;
+        let mut storage = S::new();
+        for _ in 0..len {
+            storage.insert(try!(u64::read(r)) as usize, try!(CerealData::read(r)));
+        }
+        Ok(ComponentList(storage, Changes::new(), PhantomData))
     }
 }
 
-#[cfg(feature="serialisation")]
-unsafe impl<T: Component> CerealData for InnerComponentList<T> where T: CerealData {
-    fn write(&self, w: &mut Write) -> CerealResult<()> {
-        match *self {
-            Hot(ref list) => {
-                try!(1u8.write(w));
-                try!((list.len() as u64).write(w));
-                for (idx, data) in list {
-                    try!((idx as u64).write(w));
-                    try!(data.write(w));
-                }
-            },
-            Cold(ref list) => {
-                try!(2u8.write(w));
-                try!((list.len() as u64).write(w));
-                for (idx, data) in list {
-                    try!((*idx as u64).write(w));
-                    try!(data.write(w));
-                }
-            },
+// Saved as a sequence of (entity index, component) pairs, so lists can move between storages.
+#[cfg(feature="serde")]
+impl<C: ComponentManager, T: Component, S: Storage<T>> Serialize for ComponentList<C, T, S> where T: Serialize
+{
+    fn serialize<R: Serializer>(&self, serializer: R) -> Result<R::Ok, R::Error>
+    {
+        let mut seq = try!(serializer.serialize_seq(Some(self.0.len())));
+        for component in self.0.iter()
+        {
+            try!(seq.serialize_element(&component));
         }
-        Ok(())
+        seq.end()
     }
+}
 
-    fn read(r: &mut Read) -> CerealResult<Self> {
-        let kind: u8 = try!(CerealData::read(r));
-        match kind {
-            1 => { // Hot
-                let len = try!(u64::read(r)) as usize;
-                let mut map = VecMap::with_capacity(len);
-                for _ in 0..len {
-                    map.insert(try!(u64::read(r)) as usize, try!(CerealData::read(r)));
-                }
-                Ok(Hot(map))
-            },
-            2 => { // Cold
-                let len = try!(u64::read(r)) as usize;
-                let mut map = HashMap::with_capacity(len);
-                for _ in 0..len {
-                    map.insert(try!(u64::read(r)) as usize, try!(CerealData::read(r)));
-                }
-                Ok(Cold(map))
-            },
-            x => Err(CerealError::Msg(format!("Unrecognized list type (Hot = 1, Cold = 2, Found {:?})", x))),
+#[cfg(feature="serde")]
+impl<'de, C: ComponentManager, T: Component, S: Storage<T>> Deserialize<'de> for ComponentList<C, T, S> where T: Deserialize<'de>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        let components: Vec<(usize, T)> = try!(Deserialize::deserialize(deserializer));
+        let mut storage = S::new();
+        for (index, component) in components
+        {
+            storage.insert(index, component);
         }
+        Ok(ComponentList(storage, Changes::new(), PhantomData))
+    }
+}
+
+impl<C: ComponentManager, T: Component> ComponentList<C, T, HotStorage<T>>
+{
+    pub fn hot() -> ComponentList<C, T, HotStorage<T>>
+    {
+        ComponentList::new()
     }
 }
 
-impl<C: ComponentManager, T: Component> ComponentList<C, T>
+impl<C: ComponentManager, T: Component> ComponentList<C, T, ColdStorage<T>>
 {
-    pub fn hot() -> ComponentList<C, T>
+    pub fn cold() -> ComponentList<C, T, ColdStorage<T>>
     {
-        ComponentList(Hot(VecMap::new()), PhantomData)
+        ComponentList::new()
     }
+}
 
-    pub fn cold() -> ComponentList<C, T>
+impl<C: ComponentManager, T: Component> ComponentList<C, T, DenseStorage<T>>
+{
+    pub fn dense() -> ComponentList<C, T, DenseStorage<T>>
     {
-        ComponentList(Cold(HashMap::new()), PhantomData)
+        ComponentList::new()
     }
 
-    pub fn add(&mut self, entity: &BuildData<C>, component: T) -> Option<T>
+    /// Returns every component in the list, packed together in no particular order.
+    pub fn as_slice(&self) -> &[T]
     {
-        match self.0
+        self.0.components()
+    }
+
+    /// Mutable version of `as_slice`. Every component is marked as changed.
+    pub fn as_mut_slice(&mut self) -> &mut [T]
+    {
+        for &index in self.0.indices()
         {
-            Hot(ref mut c) => c.insert(entity.0.index(), component),
-            Cold(ref mut c) => c.insert(entity.0.index(), component),
+            self.1.changed_index(index);
         }
+        self.0.components_mut()
+    }
+}
+
+impl<C: ComponentManager, T: Component, S: Storage<T> + Clone> Clone for ComponentList<C, T, S>
+{
+    fn clone(&self) -> ComponentList<C, T, S>
+    {
+        ComponentList(self.0.clone(), self.1.clone(), PhantomData)
+    }
+}
+
+impl<C: ComponentManager, T: Component, S: Storage<T>> ComponentList<C, T, S>
+{
+    pub fn new() -> ComponentList<C, T, S>
+    {
+        ComponentList(S::new(), Changes::new(), PhantomData)
+    }
+
+    pub fn add(&mut self, entity: &BuildData<C>, component: T) -> Option<T>
+    {
+        let old = self.0.insert(entity.0.index(), component);
+        self.1.inserted(entity.0, old.is_some());
+        old
     }
 
     pub fn insert(&mut self, entity: &ModifyData<C>, component: T) -> Option<T>
     {
-        match self.0
-        {
-            Hot(ref mut c) => c.insert(entity.entity().index(), component),
-            Cold(ref mut c) => c.insert(entity.entity().index(), component),
-        }
+        let old = self.0.insert(entity.entity().index(), component);
+        self.1.inserted(entity.entity(), old.is_some());
+        old
     }
 
     pub fn remove(&mut self, entity: &ModifyData<C>) -> Option<T>
     {
-        match self.0
+        let old = self.0.remove(entity.entity().index());
+        if old.is_some()
         {
-            Hot(ref mut c) => c.remove(&entity.entity().index()),
-            Cold(ref mut c) => c.remove(&entity.entity().index()),
+            self.1.removed(entity.entity());
         }
+        old
     }
 
     pub fn set<U: EditData<C>>(&mut self, entity: &U, component: T) -> Option<T>
     {
-        match self.0
+        let old = self.0.insert(entity.entity().index(), component);
+        self.1.inserted(entity.entity(), old.is_some());
+        old
+    }
+
+    pub fn get<U: EditData<C>>(&self, entity: &U) -> Option<T> where T: Clone
+    {
+        self.0.get(entity.entity().index()).cloned()
+    }
+
+    pub fn has<U: EditData<C>>(&self, entity: &U) -> bool
+    {
+        self.0.contains(entity.entity().index())
+    }
+
+    /// Mutably borrows the entity's component, marking it as changed.
+    pub fn borrow<U: EditData<C>>(&mut self, entity: &U) -> Option<&mut T>
+    {
+        let component = self.0.get_mut(entity.entity().index());
+        if component.is_some()
         {
-            Hot(ref mut c) => c.insert(entity.entity().index(), component),
-            Cold(ref mut c) => c.insert(entity.entity().index(), component),
+            self.1.changed(entity.entity());
         }
+        component
     }
 
-    pub fn get<U: EditData<C>>(&self, entity: &U) -> Option<T> where T: Clone
+    /// Returns the entity's component, or `EcsError::MissingComponent` if it has none.
+    pub fn try_index<U: EditData<C>>(&self, entity: &U) -> EcsResult<&T>
+    {
+        self.0.get(entity.entity().index()).ok_or(EcsError::MissingComponent(**entity.entity()))
+    }
+
+    /// Mutable version of `try_index`.
+    pub fn try_index_mut<U: EditData<C>>(&mut self, entity: &U) -> EcsResult<&mut T>
+    {
+        self.borrow(entity).ok_or(EcsError::MissingComponent(**entity.entity()))
+    }
+
+    /// Like `remove`, but fails if the entity had no component to remove.
+    pub fn try_remove(&mut self, entity: &ModifyData<C>) -> EcsResult<T>
+    {
+        self.remove(entity).ok_or(EcsError::MissingComponent(**entity.entity()))
+    }
+
+    /// Returns the storage holding the components.
+    pub fn storage(&self) -> &S
     {
-        match self.0
+        &self.0
+    }
+
+    /// Returns the list's change tracking.
+    pub fn changes(&self) -> &Changes<C>
+    {
+        &self.1
+    }
+
+    /// Starts tracking changes to a component that was loaded rather than added.
+    pub fn __loaded(&mut self, entity: &IndexedEntity<C>)
+    {
+        if self.0.get(entity.index()).is_some()
         {
-            Hot(ref c) => c.get(&entity.entity().index()).cloned(),
-            Cold(ref c) => c.get(&entity.entity().index()).cloned(),
+            self.1.loaded(entity);
         }
     }
 
-    pub fn has<U: EditData<C>>(&self, entity: &U) -> bool
+    /// Takes over from `current`, stamping whatever differs from it as changed or removed.
+    pub fn __rewind(&mut self, current: &ComponentList<C, T, S>)
+    {
+        self.1.rewind(&current.1);
+    }
+
+    pub fn __clear(&mut self, entity: &IndexedEntity<C>)
     {
-        match self.0
+        let old = self.0.remove(entity.index());
+        if old.is_some()
         {
-            Hot(ref c) => c.contains_key(&entity.entity().index()),
-            Cold(ref c) => c.contains_key(&entity.entity().index()),
+            self.1.removed(entity);
         }
     }
 
-    pub fn borrow<U: EditData<C>>(&mut self, entity: &U) -> Option<&mut T>
+    pub fn __maintain(&mut self)
     {
-        match self.0
+        self.1.maintain();
+    }
+
+    pub fn __merge<F>(&mut self, other: &mut ComponentList<C, T, S>, merge: &__Merge<C>, map: F)
+        where F: Fn(&mut T)
+    {
+        let indices: Vec<usize> = other.0.iter().map(|(index, _)| index).collect();
+        for index in indices
         {
-            Hot(ref mut c) => c.get_mut(&entity.entity().index()),
-            Cold(ref mut c) => c.get_mut(&entity.entity().index()),
+            if let (Some(entity), Some(mut component)) = (merge.indices.get(&index), other.0.remove(index))
+            {
+                map(&mut component);
+                let old = self.0.insert(entity.index(), component);
+                self.1.inserted(entity, old.is_some());
+            }
         }
     }
 
-    pub fn __clear(&mut self, entity: &IndexedEntity<C>)
+    pub fn __clone_entity<F>(&mut self, from: &IndexedEntity<C>, to: &IndexedEntity<C>, clone: F)
+        where F: FnOnce(&T) -> Option<T>
     {
-        match self.0
+        if let Some(component) = self.0.get(from.index()).and_then(clone)
         {
-            Hot(ref mut c) => c.remove(&entity.index()),
-            Cold(ref mut c) => c.remove(&entity.index()),
-        };
+            let old = self.0.insert(to.index(), component);
+            self.1.inserted(to, old.is_some());
+        }
     }
 }
 
-impl<C: ComponentManager, T: Component, U: EditData<C>> Index<U> for ComponentList<C, T>
+// Lets `components!` clone the components that implement `Clone`, and skip the rest.
+// Method lookup tries `&__Cloner<T>` as the receiver before `&&__Cloner<T>`, so the `Clone`
+// impl wins whenever it applies.
+#[doc(hidden)]
+pub struct __Cloner<T>(PhantomData<T>);
+
+impl<T> __Cloner<T>
 {
-    type Output = T;
-    fn index(&self, en: U) -> &T
+    pub fn new() -> __Cloner<T>
     {
-        match self.0
+        __Cloner(PhantomData)
+    }
+}
+
+#[doc(hidden)]
+pub trait __CloneComponent<T>
+{
+    fn __clone_component(&self, &T) -> Option<T>;
+}
+
+impl<T: Clone> __CloneComponent<T> for __Cloner<T>
+{
+    fn __clone_component(&self, component: &T) -> Option<T>
+    {
+        Some(component.clone())
+    }
+}
+
+#[doc(hidden)]
+pub trait __SkipComponent<T>
+{
+    fn __clone_component(&self, &T) -> Option<T>;
+}
+
+impl<'a, T> __SkipComponent<T> for &'a __Cloner<T>
+{
+    fn __clone_component(&self, _: &T) -> Option<T>
+    {
+        None
+    }
+}
+
+// Like `__Cloner`, but for components that implement `MapEntities`.
+#[doc(hidden)]
+pub struct __Mapper<T>(PhantomData<T>);
+
+impl<T> __Mapper<T>
+{
+    pub fn of(_: &T) -> __Mapper<T>
+    {
+        __Mapper(PhantomData)
+    }
+}
+
+#[doc(hidden)]
+pub trait __MapComponent<T>
+{
+    fn __map_component(&self, &mut T, &EntityMap);
+}
+
+impl<T: MapEntities> __MapComponent<T> for __Mapper<T>
+{
+    fn __map_component(&self, component: &mut T, map: &EntityMap)
+    {
+        component.map_entities(map);
+    }
+}
+
+#[doc(hidden)]
+pub trait __SkipMapping<T>
+{
+    fn __map_component(&self, &mut T, &EntityMap);
+}
+
+impl<'a, T> __SkipMapping<T> for &'a __Mapper<T>
+{
+    fn __map_component(&self, _: &mut T, _: &EntityMap)
+    {
+
+    }
+}
+
+impl<C: ComponentManager> Clone for Changes<C>
+{
+    fn clone(&self) -> Changes<C>
+    {
+        let mut stamps = VecMap::new();
+        for (index, stamp) in self.stamps.iter()
+        {
+            stamps.insert(index, Stamp { entity: stamp.entity.__clone(), added: stamp.added, changed: stamp.changed });
+        }
+        Changes
         {
-            Hot(ref c) => &c[en.entity().index()],
-            Cold(ref c) => &c[&en.entity().index()],
+            tick: self.tick,
+            stamps: stamps,
+            removed: self.removed.clone(),
+            horizon: self.horizon,
         }
     }
 }
 
-impl<C: ComponentManager, T: Component, U: EditData<C>> IndexMut<U> for ComponentList<C, T>
+impl<C: ComponentManager> Changes<C>
 {
-    fn index_mut(&mut self, en: U) -> &mut T
+    fn new() -> Changes<C>
+    {
+        Changes
+        {
+            tick: 0,
+            stamps: VecMap::new(),
+            removed: Vec::new(),
+            horizon: 0,
+        }
+    }
+
+    /// Returns the current value of the list's clock.
+    pub fn tick(&self) -> Tick
+    {
+        self.tick
+    }
+
+    /// Iterates over entities whose component was added or changed after `tick`.
+    pub fn changed_since(&self, tick: Tick) -> ChangedIter<C>
+    {
+        ChangedIter
+        {
+            inner: self.stamps.values(),
+            since: tick,
+            added_only: false,
+        }
+    }
+
+    /// Iterates over entities whose component was added after `tick`.
+    pub fn added_since(&self, tick: Tick) -> ChangedIter<C>
     {
-        match self.0
+        ChangedIter
         {
-            Hot(ref mut c) => c.get_mut(&en.entity().index()),
-            Cold(ref mut c) => c.get_mut(&en.entity().index()),
-        }.expect(&format!("Could not find entry for {:?}", **en.entity()))
+            inner: self.stamps.values(),
+            since: tick,
+            added_only: true,
+        }
+    }
+
+    /// Iterates over entities that lost their component after `tick`.
+    ///
+    /// The entities may have been removed from the world as well.
+    pub fn removed_since(&self, tick: Tick) -> RemovedIter
+    {
+        let start = self.removed.iter().position(|&(_, t)| t > tick).unwrap_or(self.removed.len());
+        RemovedIter(self.removed[start..].iter())
+    }
+
+    fn inserted(&mut self, entity: &IndexedEntity<C>, replaced: bool)
+    {
+        if replaced
+        {
+            self.changed(entity);
+        }
+        else
+        {
+            self.tick += 1;
+            self.stamps.insert(entity.index(), Stamp
+            {
+                entity: entity.__clone(),
+                added: self.tick,
+                changed: self.tick,
+            });
+        }
+    }
+
+    fn loaded(&mut self, entity: &IndexedEntity<C>)
+    {
+        if !self.stamps.contains_key(&entity.index())
+        {
+            self.stamps.insert(entity.index(), Stamp
+            {
+                entity: entity.__clone(),
+                added: 0,
+                changed: 0,
+            });
+        }
+    }
+
+    // Everything the rollback changes is stamped at a tick after all that watchers have
+    // already seen, so they notice values going back. Untouched components keep their stamps.
+    fn rewind(&mut self, current: &Changes<C>)
+    {
+        self.tick = cmp::max(self.tick, current.tick) + 1;
+        let tick = self.tick;
+        for (index, stamp) in self.stamps.iter_mut()
+        {
+            match current.stamps.get(&index)
+            {
+                Some(now) if *now.entity == *stamp.entity =>
+                    if now.changed != stamp.changed
+                    {
+                        stamp.changed = tick;
+                    },
+                _ => {
+                    stamp.added = tick;
+                    stamp.changed = tick;
+                },
+            }
+        }
+        // The removal log carries on from the current one, plus whatever the rollback took away.
+        self.removed = current.removed.clone();
+        self.horizon = current.horizon;
+        for (index, now) in current.stamps.iter()
+        {
+            match self.stamps.get(&index)
+            {
+                Some(stamp) if *stamp.entity == *now.entity => {},
+                _ => self.removed.push((*now.entity, tick)),
+            }
+        }
+    }
+
+    fn changed(&mut self, entity: &IndexedEntity<C>)
+    {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(stamp) = self.stamps.get_mut(&entity.index())
+        {
+            stamp.changed = tick;
+            return;
+        }
+        // Components loaded from a save have no stamp yet.
+        self.stamps.insert(entity.index(), Stamp
+        {
+            entity: entity.__clone(),
+            added: 0,
+            changed: tick,
+        });
+    }
+
+    // Joins only know the index, so this relies on every component having a stamp. Loaded
+    // components get theirs from `__loaded`.
+    fn changed_index(&mut self, index: usize)
+    {
+        self.tick += 1;
+        let tick = self.tick;
+        match self.stamps.get_mut(&index)
+        {
+            Some(stamp) => stamp.changed = tick,
+            None => debug_assert!(false, "component at index {} has no change stamp", index),
+        }
+    }
+
+    fn removed(&mut self, entity: &IndexedEntity<C>)
+    {
+        self.tick += 1;
+        self.stamps.remove(&entity.index());
+        self.removed.push((**entity, self.tick));
+    }
+
+    fn maintain(&mut self)
+    {
+        let horizon = self.horizon;
+        self.removed.retain(|&(_, t)| t > horizon);
+        self.horizon = self.tick;
+    }
+}
+
+pub struct ChangedIter<'a, C: ComponentManager>
+{
+    inner: ::vec_map::Values<'a, Stamp<C>>,
+    since: Tick,
+    added_only: bool,
+}
+
+impl<'a, C: ComponentManager> Iterator for ChangedIter<'a, C>
+{
+    type Item = EntityData<'a, C>;
+    fn next(&mut self) -> Option<EntityData<'a, C>>
+    {
+        for stamp in &mut self.inner
+        {
+            let tick = if self.added_only { stamp.added } else { stamp.changed };
+            if tick > self.since
+            {
+                return Some(EntityData(&stamp.entity));
+            }
+        }
+        None
+    }
+}
+
+pub struct RemovedIter<'a>(::std::slice::Iter<'a, (Entity, Tick)>);
+
+impl<'a> Iterator for RemovedIter<'a>
+{
+    type Item = Entity;
+    fn next(&mut self) -> Option<Entity>
+    {
+        self.0.next().map(|&(entity, _)| entity)
+    }
+}
+
+impl<C: ComponentManager, T: Component, S: Storage<T>, U: EditData<C>> Index<U> for ComponentList<C, T, S>
+{
+    type Output = T;
+    fn index(&self, en: U) -> &T
+    {
+        self.0.get(en.entity().index()).expect(&format!("Could not find entry for {:?}", **en.entity()))
+    }
+}
+
+impl<C: ComponentManager, T: Component, S: Storage<T>, U: EditData<C>> IndexMut<U> for ComponentList<C, T, S>
+{
+    fn index_mut(&mut self, en: U) -> &mut T
+    {
+        let entity = **en.entity();
+        self.borrow(&en).expect(&format!("Could not find entry for {:?}", entity))
+    }
+}
+
+impl<'a, C: ComponentManager, T: Component, S: Storage<T>> Joinable for &'a ComponentList<C, T, S>
+{
+    type Item = &'a T;
+
+    fn __len(&self) -> usize
+    {
+        self.0.len()
+    }
+
+    fn __indices(&self) -> Vec<usize>
+    {
+        self.0.iter().map(|(i, _)| i).collect()
+    }
+
+    fn __contains(&self, index: usize) -> bool
+    {
+        self.0.contains(index)
+    }
+
+    unsafe fn __fetch(&mut self, index: usize) -> &'a T
+    {
+        let list: &'a ComponentList<C, T, S> = *self;
+        list.0.get(index).unwrap()
+    }
+}
+
+impl<'a, C: ComponentManager, T: Component, S: Storage<T>> Joinable for &'a mut ComponentList<C, T, S>
+{
+    type Item = &'a mut T;
+
+    fn __len(&self) -> usize
+    {
+        self.0.len()
+    }
+
+    fn __indices(&self) -> Vec<usize>
+    {
+        self.0.iter().map(|(i, _)| i).collect()
+    }
+
+    fn __contains(&self, index: usize) -> bool
+    {
+        self.0.contains(index)
+    }
+
+    unsafe fn __fetch(&mut self, index: usize) -> &'a mut T
+    {
+        self.1.changed_index(index);
+        let component: *mut T = self.0.get_mut(index).unwrap();
+        &mut *component
     }
 }
 
@@ -223,3 +697,4 @@ impl<T: ComponentManager, F> EntityModifier<T> for F where F: FnOnce(ModifyData<
 }
 
 impl<T: ComponentManager> EntityModifier<T> for () { fn modify(self, _: ModifyData<T>, _: &mut T) {} }
+
@@ -0,0 +1,320 @@
+//! The differences between two states of a world.
+//!
+//! A `Delta` records the entities created and removed between two states, and the components
+//! added, changed and removed in each list. Applying it to a world in the first state brings
+//! it to the second:
+//!
+//! ```ignore
+//! let base = world.snapshot();
+//! world.update();
+//! let delta = Delta::between(&base, &world)?;
+//! other.apply_delta(&delta)?;
+//! ```
+//!
+//! States are compared through their serialised form, so anything that saves like a world can
+//! be compared: a `World`, a `DataHelper` or a `Snapshot`. Components are held as `Value`s,
+//! so a delta must be saved in a self-describing format such as JSON or RON; formats like
+//! bincode can't read one back.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_value::{self, Value};
+use std::collections::{BTreeMap, HashSet};
+
+use Entity;
+use entity::Generation;
+
+/// The changes to the components of one list, by entity index.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ComponentDelta
+{
+    pub added: Vec<(usize, Value)>,
+    pub changed: Vec<(usize, Value)>,
+    pub removed: Vec<usize>,
+}
+
+impl ComponentDelta
+{
+    pub fn is_empty(&self) -> bool
+    {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Everything needed to bring a world from one state to another.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delta
+{
+    created: Vec<Entity>,
+    removed: Vec<Entity>,
+    // Recycled indices, the next index and the generations that changed.
+    indices: Option<(Vec<usize>, usize, Vec<(usize, Generation)>)>,
+    links: Option<Vec<(Entity, Entity)>>,
+    queue: Option<Value>,
+    services: Option<Value>,
+    components: BTreeMap<String, ComponentDelta>,
+}
+
+impl Delta
+{
+    /// Compares two states of a world.
+    pub fn between<A: Serialize, B: Serialize>(old: &A, new: &B) -> Result<Delta, String>
+    {
+        let old = try!(State::read(try!(encode(old))));
+        let new = try!(State::read(try!(encode(new))));
+
+        let old_live: HashSet<Entity> = old.live.iter().cloned().collect();
+        let new_live: HashSet<Entity> = new.live.iter().cloned().collect();
+        let indices = if (&old.recycled, old.next_index, &old.generations) == (&new.recycled, new.next_index, &new.generations)
+        {
+            None
+        }
+        else
+        {
+            let generations = new.generations.iter().enumerate()
+                .filter(|&(i, g)| old.generations.get(i) != Some(g))
+                .map(|(i, &g)| (i, g))
+                .collect();
+            Some((new.recycled.clone(), new.next_index, generations))
+        };
+
+        // Components are only compared when the same entity holds them in both states. When an
+        // index was recycled, the old entity's components are removed and the new one's added.
+        let old_owners: BTreeMap<usize, Entity> = old.live.iter().map(|e| (e.index(), *e)).collect();
+        let new_owners: BTreeMap<usize, Entity> = new.live.iter().map(|e| (e.index(), *e)).collect();
+        let same = |index: &usize| old_owners.get(index) == new_owners.get(index);
+        let mut components = BTreeMap::new();
+        for (name, new_list) in new.lists
+        {
+            let old_list = old.lists.get(&name).cloned().unwrap_or(BTreeMap::new());
+            let mut list = ComponentDelta::default();
+            for (&index, component) in &new_list
+            {
+                match old_list.get(&index)
+                {
+                    Some(old) if same(&index) => if old != component
+                    {
+                        list.changed.push((index, component.clone()));
+                    },
+                    _ => list.added.push((index, component.clone())),
+                }
+            }
+            list.removed = old_list.keys().filter(|i| !new_list.contains_key(i) || !same(i)).cloned().collect();
+            if !list.is_empty()
+            {
+                components.insert(name, list);
+            }
+        }
+
+        Ok(Delta
+        {
+            created: new.live.iter().filter(|e| !old_live.contains(e)).cloned().collect(),
+            removed: old.live.iter().filter(|e| !new_live.contains(e)).cloned().collect(),
+            indices: indices,
+            links: if old.links != new.links { Some(new.links) } else { None },
+            queue: if old.queue != new.queue { Some(new.queue) } else { None },
+            services: if old.services != new.services { Some(new.services) } else { None },
+            components: components,
+        })
+    }
+
+    /// Returns the entities that exist in the new state but not the old one.
+    pub fn created(&self) -> &[Entity]
+    {
+        &self.created
+    }
+
+    /// Returns the entities that exist in the old state but not the new one.
+    pub fn removed(&self) -> &[Entity]
+    {
+        &self.removed
+    }
+
+    /// Returns the changes to a component list, named after its field in `components!`.
+    pub fn components(&self, list: &str) -> Option<&ComponentDelta>
+    {
+        self.components.get(list)
+    }
+
+    /// Returns true if both states were the same.
+    pub fn is_empty(&self) -> bool
+    {
+        self.created.is_empty() && self.removed.is_empty() && self.indices.is_none() && self.links.is_none()
+            && self.queue.is_none() && self.services.is_none() && self.components.is_empty()
+    }
+
+    #[doc(hidden)]
+    pub fn __components(&self) -> &BTreeMap<String, ComponentDelta>
+    {
+        &self.components
+    }
+
+    #[doc(hidden)]
+    pub fn __touched(&self) -> HashSet<usize>
+    {
+        let mut touched: HashSet<usize> = self.created.iter().chain(&self.removed).map(|e| e.index()).collect();
+        for list in self.components.values()
+        {
+            touched.extend(list.added.iter().chain(&list.changed).map(|&(i, _)| i));
+            touched.extend(list.removed.iter().cloned());
+        }
+        touched
+    }
+
+    /// Applies the delta to the serialised form of a world.
+    #[doc(hidden)]
+    pub fn __apply(&self, value: Value) -> Result<Value, String>
+    {
+        let mut state = try!(State::read(value));
+        let removed: HashSet<Entity> = self.removed.iter().cloned().collect();
+        state.live.retain(|e| !removed.contains(e));
+        state.live.extend(self.created.iter().cloned());
+        if let Some((ref recycled, next_index, ref generations)) = self.indices
+        {
+            state.recycled = recycled.clone();
+            state.next_index = next_index;
+            state.generations.resize(next_index, 0);
+            for &(index, generation) in generations
+            {
+                match state.generations.get_mut(index)
+                {
+                    Some(slot) => *slot = generation,
+                    None => return Err(format!("entity index {} is out of range", index)),
+                }
+            }
+        }
+        if let Some(ref links) = self.links
+        {
+            state.links = links.clone();
+        }
+        if let Some(ref queue) = self.queue
+        {
+            state.queue = queue.clone();
+        }
+        if let Some(ref services) = self.services
+        {
+            state.services = services.clone();
+        }
+        for (name, changes) in &self.components
+        {
+            let list = match state.lists.get_mut(name)
+            {
+                Some(list) => list,
+                None => return Err(format!("unknown component {:?}", name)),
+            };
+            for index in &changes.removed
+            {
+                list.remove(index);
+            }
+            for &(index, ref component) in changes.added.iter().chain(&changes.changed)
+            {
+                list.insert(index, component.clone());
+            }
+        }
+        state.write()
+    }
+}
+
+// A world's serialised form, taken apart.
+struct State
+{
+    services: Value,
+    recycled: Vec<usize>,
+    next_index: usize,
+    generations: Vec<Generation>,
+    live: Vec<Entity>,
+    links: Vec<(Entity, Entity)>,
+    queue: Value,
+    lists: BTreeMap<String, BTreeMap<usize, Value>>,
+}
+
+impl State
+{
+    fn read(value: Value) -> Result<State, String>
+    {
+        let (services, (indices, live, links, queue), lists): (Value, (Value, Value, Value, Value), BTreeMap<String, Value>) =
+            try!(decode(value));
+        let (recycled, next_index, generations) = try!(decode(indices));
+        let mut components = BTreeMap::new();
+        for (name, list) in lists
+        {
+            let entries: Vec<(usize, Value)> = try!(decode(list));
+            components.insert(name, entries.into_iter().collect());
+        }
+        Ok(State
+        {
+            services: services,
+            recycled: recycled,
+            next_index: next_index,
+            generations: generations,
+            live: try!(decode(live)),
+            links: try!(decode(links)),
+            queue: queue,
+            lists: components,
+        })
+    }
+
+    fn write(self) -> Result<Value, String>
+    {
+        let mut lists = BTreeMap::new();
+        for (name, list) in self.lists
+        {
+            lists.insert(name, list.into_iter().collect::<Vec<_>>());
+        }
+        let indices = (self.recycled, self.next_index, self.generations);
+        encode(&(self.services, (indices, self.live, self.links, self.queue), lists))
+    }
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<Value, String>
+{
+    serde_value::to_value(value).map_err(|e| e.to_string())
+}
+
+fn decode<T: for<'de> Deserialize<'de>>(value: Value) -> Result<T, String>
+{
+    value.deserialize_into().map_err(|e| e.to_string())
+}
+
+impl Serialize for ComponentDelta
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        (&self.added, &self.changed, &self.removed).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ComponentDelta
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ComponentDelta, D::Error>
+    {
+        let (added, changed, removed) = try!(Deserialize::deserialize(deserializer));
+        Ok(ComponentDelta { added: added, changed: changed, removed: removed })
+    }
+}
+
+impl Serialize for Delta
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        (&self.created, &self.removed, &self.indices, &self.links, &self.queue, &self.services, &self.components)
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Delta
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Delta, D::Error>
+    {
+        let (created, removed, indices, links, queue, services, components) = try!(Deserialize::deserialize(deserializer));
+        Ok(Delta
+        {
+            created: created,
+            removed: removed,
+            indices: indices,
+            links: links,
+            queue: queue,
+            services: services,
+            components: components,
+        })
+    }
+}
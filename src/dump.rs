@@ -0,0 +1,225 @@
+//! A text-friendly layout of a world, listing each entity with its components.
+//!
+//! Where a normal save stores each component list in turn, a dump stores each entity in turn,
+//! so it reads well as JSON or RON and changes to a level show up as small diffs:
+//!
+//! ```ignore
+//! {
+//!   "entities": [
+//!     { "id": [0, 1], "components": { "name": "door", "position": { "x": 2.0, "y": 0.0 } } },
+//!     { "id": [1, 1], "parent": [0, 1], "components": { "name": "handle" } }
+//!   ],
+//!   "services": null
+//! }
+//! ```
+//!
+//! Components are named after their field in `components!`. When loading, entities without an
+//! `id` are given a new one, and `free` (the generations of unused ids) and `queue` (entities
+//! waiting for the next flush) may be left out.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_value::{self, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use Entity;
+use entity::Generation;
+
+#[doc(hidden)]
+pub fn __dump<T: Serialize>(data: &T) -> Result<Value, String>
+{
+    let mut parts = try!(split(try!(serde_value::to_value(data).map_err(|e| e.to_string())), 3));
+    let lists = parts.pop().unwrap();
+    let mut manager = try!(split(parts.pop().unwrap(), 4));
+    let services = parts.pop().unwrap();
+    let queue = manager.pop().unwrap();
+    let links: Vec<(Entity, Entity)> = try!(decode(manager.pop().unwrap()));
+    let live: Vec<Entity> = try!(decode(manager.pop().unwrap()));
+    let (recycled, _, generations): (Vec<usize>, usize, Vec<Generation>) = try!(decode(manager.pop().unwrap()));
+
+    let mut components = BTreeMap::new();
+    for entity in &live
+    {
+        components.insert(entity.index(), BTreeMap::new());
+    }
+    let lists = match lists
+    {
+        Value::Map(lists) => lists,
+        _ => return Err("components must be saved as a struct".to_string()),
+    };
+    for (name, list) in lists
+    {
+        let entries: Vec<(usize, Value)> = try!(decode(list));
+        for (index, component) in entries
+        {
+            if let Some(entity) = components.get_mut(&index)
+            {
+                entity.insert(name.clone(), component);
+            }
+        }
+    }
+    let parents: HashMap<Entity, Entity> = links.into_iter().collect();
+
+    let mut entities = Vec::with_capacity(live.len());
+    for entity in live
+    {
+        let mut fields = BTreeMap::new();
+        fields.insert(key("id"), try!(encode(&entity)));
+        if let Some(parent) = parents.get(&entity)
+        {
+            fields.insert(key("parent"), try!(encode(parent)));
+        }
+        fields.insert(key("components"), Value::Map(components.remove(&entity.index()).unwrap()));
+        entities.push(Value::Map(fields));
+    }
+    let mut dump = BTreeMap::new();
+    dump.insert(key("entities"), Value::Seq(entities));
+    dump.insert(key("services"), services);
+    let free: Vec<(usize, Generation)> = recycled.into_iter().map(|i| (i, generations[i])).collect();
+    if !free.is_empty()
+    {
+        dump.insert(key("free"), try!(encode(&free)));
+    }
+    if queue != Value::Seq(Vec::new())
+    {
+        dump.insert(key("queue"), queue);
+    }
+    Ok(Value::Map(dump))
+}
+
+/// Turns a dump back into the layout of a normal save. `empty` is a fresh components struct,
+/// which tells which component names exist.
+#[doc(hidden)]
+pub fn __undump<T: DeserializeOwned, C: Serialize>(dump: Value, empty: &C) -> Result<T, String>
+{
+    let mut dump = match dump
+    {
+        Value::Map(dump) => dump,
+        _ => return Err("expected a map of entities and services".to_string()),
+    };
+    let services = dump.remove(&key("services")).unwrap_or(Value::Unit);
+    let free: Vec<(usize, Generation)> = match dump.remove(&key("free")) { Some(free) => try!(decode(free)), None => Vec::new() };
+    let queue = dump.remove(&key("queue")).unwrap_or(Value::Seq(Vec::new()));
+    let entries = match dump.remove(&key("entities"))
+    {
+        Some(Value::Seq(entries)) => entries,
+        None => Vec::new(),
+        Some(_) => return Err("`entities` must be a list".to_string()),
+    };
+
+    let mut lists = match try!(serde_value::to_value(empty).map_err(|e| e.to_string()))
+    {
+        Value::Map(lists) => lists,
+        _ => return Err("components must be saved as a struct".to_string()),
+    };
+
+    // Read every entry first, so entities without an id can be put after the others.
+    let mut parsed = Vec::with_capacity(entries.len());
+    for entry in entries
+    {
+        let mut fields = match entry
+        {
+            Value::Map(fields) => fields,
+            _ => return Err("each entity must be a map".to_string()),
+        };
+        let id: Option<Entity> = match fields.remove(&key("id")) { Some(id) => Some(try!(decode(id))), None => None };
+        let parent: Option<Entity> = match fields.remove(&key("parent")) { Some(parent) => Some(try!(decode(parent))), None => None };
+        let components = match fields.remove(&key("components"))
+        {
+            Some(Value::Map(components)) => components,
+            None => BTreeMap::new(),
+            Some(_) => return Err("`components` must be a map".to_string()),
+        };
+        parsed.push((id, parent, components));
+    }
+    let mut next_index = 0;
+    for &(id, _, _) in &parsed
+    {
+        if let Some(id) = id
+        {
+            next_index = ::std::cmp::max(next_index, id.index() + 1);
+        }
+    }
+    for &(index, _) in &free
+    {
+        next_index = ::std::cmp::max(next_index, index + 1);
+    }
+
+    let mut generations = vec![1; next_index];
+    for &(index, generation) in &free
+    {
+        generations[index] = generation;
+    }
+    let mut used = BTreeSet::new();
+    let mut live = Vec::with_capacity(parsed.len());
+    let mut links = Vec::new();
+    for (id, parent, components) in parsed
+    {
+        let (index, generation) = match id
+        {
+            Some(id) => (id.index(), id.generation()),
+            None => {
+                generations.push(1);
+                (generations.len() - 1, 1)
+            },
+        };
+        if generation == 0
+        {
+            return Err(format!("entity {} has generation 0, which only `Entity::nil()` has", index));
+        }
+        if !used.insert(index)
+        {
+            return Err(format!("more than one entity has index {}", index));
+        }
+        generations[index] = generation;
+        let entity: Entity = try!(decode(Value::Seq(vec![Value::U64(index as u64), Value::U64(generation as u64)])));
+        for (name, component) in components
+        {
+            match lists.get_mut(&name)
+            {
+                Some(&mut Value::Seq(ref mut list)) => list.push(Value::Seq(vec![Value::U64(index as u64), component])),
+                _ => return Err(format!("unknown component {:?}", name)),
+            }
+        }
+        if let Some(parent) = parent
+        {
+            links.push((entity, parent));
+        }
+        live.push(entity);
+    }
+    // Lower indices are reused first.
+    let recycled: Vec<usize> = (0..generations.len()).rev().filter(|i| !used.contains(i)).collect();
+    let next_index = generations.len();
+
+    let manager = Value::Seq(vec![
+        try!(encode(&(recycled, next_index, generations))),
+        try!(encode(&live)),
+        try!(encode(&links)),
+        queue,
+    ]);
+    decode(Value::Seq(vec![services, manager, Value::Map(lists)]))
+}
+
+fn key(name: &str) -> Value
+{
+    Value::String(name.to_string())
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<Value, String>
+{
+    serde_value::to_value(value).map_err(|e| e.to_string())
+}
+
+fn decode<T: DeserializeOwned>(value: Value) -> Result<T, String>
+{
+    value.deserialize_into().map_err(|e| e.to_string())
+}
+
+fn split(value: Value, len: usize) -> Result<Vec<Value>, String>
+{
+    match value
+    {
+        Value::Seq(parts) if parts.len() == len => Ok(parts),
+        _ => Err("unexpected layout of saved world".to_string()),
+    }
+}
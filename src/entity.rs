@@ -2,27 +2,75 @@
 //! Entity identifier and manager types.
 
 #[cfg(feature="serialisation")] use cereal::{CerealData, CerealError, CerealResult};
+#[cfg(feature="serde")] use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature="serde")] use serde::de::Error as DeError;
 
-use std::collections::hash_map::{HashMap, Values};
+use std::any::TypeId;
+use std::collections::HashMap;
 use std::default::Default;
 use std::marker::PhantomData;
 use std::ops::Deref;
+use std::slice;
+use vec_map::{self, VecMap};
 
 use Aspect;
 use BuildData;
 use ComponentManager;
 use EntityData;
 use EntityBuilder;
+use {EcsError, EcsResult};
+use event::AnyChannel;
+use map::{EntityMap, __Merge};
+use relation::{AnyRelations, Relations};
 use ServiceManager;
 use SystemManager;
 
 pub type Id = u64;
 
+/// Counts how many times an entity index has been recycled.
+///
+/// Generation `0` is never given to a live entity, so it can be used by `Entity::nil()`.
+pub type Generation = u32;
+
+/// Generational entity handle.
+///
+/// The index locates the entity's components and is recycled once the entity is removed.
+/// The generation is bumped every time that happens, so a handle kept around after its entity
+/// was removed will never match the entity that reuses the index.
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
-pub struct Entity(Id);
+pub struct Entity(u32, Generation);
 
+// TODO: Cleanup
 #[cfg(feature="serialisation")]
-impl_cereal_data!(Entity(), a);
+unsafe impl CerealData for Entity {
+    fn write(&self, write: &mut ::std::io::Write) -> CerealResult<()> {
+        try!((self.0 as u64).write(write));
+        (self.1 as u64).write(write)
+    }
+
+    fn read(read: &mut ::std::io::Read) -> CerealResult<Entity> {
+        Ok(Entity(try!(u64::read(read)) as u32, try!(u64::read(read)) as Generation))
+    }
+}
+
+#[cfg(feature="serde")]
+impl Serialize for Entity
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        (self.0, self.1).serialize(serializer)
+    }
+}
+
+#[cfg(feature="serde")]
+impl<'de> Deserialize<'de> for Entity
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Entity, D::Error>
+    {
+        let (index, generation) = try!(Deserialize::deserialize(deserializer));
+        Ok(Entity(index, generation))
+    }
+}
 
 #[derive(Debug, Eq, Hash, PartialEq)]
 pub struct IndexedEntity<T: ComponentManager>(usize, Entity, PhantomData<T>);
@@ -44,14 +92,30 @@ impl Entity
 {
     pub fn nil() -> Entity
     {
-        Entity(0)
+        Entity(0, 0)
     }
 
     /// Returns the entity's unique identifier.
+    ///
+    /// This packs the generation and the index together, so it is never shared by two entities.
     #[inline]
     pub fn id(&self) -> Id
     {
-        self.0
+        ((self.1 as Id) << 32) | self.0 as Id
+    }
+
+    /// Returns the index used to store the entity's components.
+    #[inline]
+    pub fn index(&self) -> usize
+    {
+        self.0 as usize
+    }
+
+    /// Returns how many times the entity's index had been recycled when it was created.
+    #[inline]
+    pub fn generation(&self) -> Generation
+    {
+        self.1
     }
 }
 
@@ -96,7 +160,8 @@ pub struct FilteredEntityIter<'a, T: ComponentManager>
 // Inner Entity Iterator
 pub enum EntityIter<'a, T: ComponentManager>
 {
-    Map(Values<'a, Entity, IndexedEntity<T>>),
+    Indexed(vec_map::Values<'a, IndexedEntity<T>>),
+    Slice(slice::Iter<'a, IndexedEntity<T>>),
 }
 
 impl<'a, T: ComponentManager> EntityIter<'a, T>
@@ -112,8 +177,10 @@ impl<'a, T: ComponentManager> EntityIter<'a, T>
     }
 
     pub fn clone(&self) -> Self {
-        let EntityIter::Map(ref values) = *self;
-        EntityIter::Map(values.clone())
+        match *self {
+            EntityIter::Indexed(ref values) => EntityIter::Indexed(values.clone()),
+            EntityIter::Slice(ref values) => EntityIter::Slice(values.clone()),
+        }
     }
 }
 
@@ -124,7 +191,8 @@ impl<'a, T: ComponentManager> Iterator for EntityIter<'a, T>
     {
         match *self
         {
-            EntityIter::Map(ref mut values) => values.next().map(|x| EntityData(x))
+            EntityIter::Indexed(ref mut values) => values.next().map(|x| EntityData(x)),
+            EntityIter::Slice(ref mut values) => values.next().map(|x| EntityData(x)),
         }
     }
 }
@@ -149,42 +217,202 @@ impl<'a, T: ComponentManager> Iterator for FilteredEntityIter<'a, T>
     }
 }
 
+#[derive(Clone)]
 enum Event
 {
     BuildEntity(Entity),
     RemoveEntity(Entity),
 }
 
+// Events are saved as a kind (Build = 0, Remove = 1) and the entity.
+impl Event
+{
+    #[cfg(any(feature="serialisation", feature="serde"))]
+    fn kind(&self) -> (u8, Entity)
+    {
+        match *self
+        {
+            Event::BuildEntity(entity) => (0, entity),
+            Event::RemoveEntity(entity) => (1, entity),
+        }
+    }
+
+    #[cfg(any(feature="serialisation", feature="serde"))]
+    fn from_kind(kind: u8, entity: Entity) -> Option<Event>
+    {
+        match kind
+        {
+            0 => Some(Event::BuildEntity(entity)),
+            1 => Some(Event::RemoveEntity(entity)),
+            _ => None,
+        }
+    }
+}
+
 /// Handles creation, activation, and validating of entities.
 #[doc(hidden)]
 pub struct EntityManager<T: ComponentManager>
 {
     indices: IndexPool,
-    entities: HashMap<Entity, IndexedEntity<T>>,
+    entities: VecMap<IndexedEntity<T>>,
+    removing: VecMap<()>,
     event_queue: Vec<Event>,
-    next_id: Id,
+    parents: VecMap<Entity>,
+    children: VecMap<Vec<Entity>>,
+    relations: HashMap<TypeId, Box<AnyRelations>>,
 }
 
 // TODO: Cleanup
 #[cfg(feature="serialisation")]
 unsafe impl<T: ComponentManager> CerealData for EntityManager<T> {
     fn write(&self, write: &mut ::std::io::Write) -> CerealResult<()> {
-        if self.event_queue.len() != 0 {
-            Err(CerealError::Msg("Please flush events before serialising the world".to_string()))
-        } else {
-            try!(self.indices.write(write));
-            try!(self.entities.write(write));
-            self.next_id.write(write)
+        try!(self.indices.write(write));
+        try!((self.entities.len() as u64).write(write));
+        for entity in self.entities.values() {
+            try!(entity.write(write));
+        }
+        try!((self.parents.len() as u64).write(write));
+        for (_, children) in self.children.iter() {
+            for child in children {
+                try!(child.write(write));
+                try!(self.parents[child.index()].write(write));
+            }
         }
+        try!((self.event_queue.len() as u64).write(write));
+        for event in &self.event_queue {
+            let (kind, entity) = event.kind();
+            try!(kind.write(write));
+            try!(entity.write(write));
+        }
+        Ok(())
     }
 
     fn read(read: &mut ::std::io::Read) -> CerealResult<EntityManager<T>> {
-        Ok(EntityManager {
-            indices: try!(CerealData::read(read)),
-            entities: try!(CerealData::read(read)),
-            next_id: try!(CerealData::read(read)),
+        let indices = try!(CerealData::read(read));
+        let len = try!(u64::read(read)) as usize;
+        let mut entities = VecMap::with_capacity(len);
+        for _ in 0..len {
+            let entity: IndexedEntity<T> = try!(CerealData::read(read));
+            entities.insert(entity.index(), entity);
+        }
+        let mut manager = EntityManager {
+            indices: indices,
+            entities: entities,
+            removing: VecMap::new(),
             event_queue: Vec::new(),
-        })
+            parents: VecMap::new(),
+            children: VecMap::new(),
+            relations: HashMap::new(),
+        };
+        let len = try!(u64::read(read)) as usize;
+        for _ in 0..len {
+            let child: Entity = try!(CerealData::read(read));
+            let parent: Entity = try!(CerealData::read(read));
+            try!(manager.set_parent(child, Some(parent)).map_err(|e| CerealError::Msg(e.to_string())));
+        }
+        let len = try!(u64::read(read)) as usize;
+        let mut queue = Vec::with_capacity(len);
+        for _ in 0..len {
+            let kind: u8 = try!(CerealData::read(read));
+            let entity: Entity = try!(CerealData::read(read));
+            match Event::from_kind(kind, entity) {
+                Some(event) => queue.push(event),
+                None => return Err(CerealError::Msg(format!("Unrecognized event type (Build = 0, Remove = 1, Found {:?})", kind))),
+            }
+        }
+        manager.restore_queue(queue);
+        Ok(manager)
+    }
+}
+
+// Saved as the index pool, the live entities, each (child, parent) pair, then the events
+// waiting for the next flush.
+#[cfg(feature="serde")]
+impl<T: ComponentManager> Serialize for EntityManager<T>
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        let entities: Vec<Entity> = self.entities.values().map(|e| e.1).collect();
+        let mut links = Vec::with_capacity(self.parents.len());
+        for (_, children) in self.children.iter()
+        {
+            for child in children
+            {
+                links.push((*child, self.parents[child.index()]));
+            }
+        }
+        let queue: Vec<(u8, Entity)> = self.event_queue.iter().map(|e| e.kind()).collect();
+        (&self.indices, entities, links, queue).serialize(serializer)
+    }
+}
+
+#[cfg(feature="serde")]
+impl<'de, T: ComponentManager> Deserialize<'de> for EntityManager<T>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<EntityManager<T>, D::Error>
+    {
+        let (indices, entities, links, queue): (IndexPool, Vec<Entity>, Vec<(Entity, Entity)>, Vec<(u8, Entity)>) =
+            try!(Deserialize::deserialize(deserializer));
+        let mut manager = EntityManager::new();
+        for entity in entities
+        {
+            // Every live entity must hold the current generation of an index that isn't free,
+            // or the pool would hand its index out again.
+            let index = entity.index();
+            if indices.generations.get(index) != Some(&entity.generation()) || indices.recycled.contains(&index)
+                || manager.entities.contains_key(&index)
+            {
+                return Err(D::Error::custom(format!("{:?} doesn't match the entity indices", entity)));
+            }
+            manager.entities.insert(index, IndexedEntity(index, entity, PhantomData));
+        }
+        // Together with the checks above, every index is now either live or free, never both.
+        if manager.entities.len() + indices.recycled.len() != indices.next_index
+        {
+            return Err(D::Error::custom("some entity indices are neither live nor free"));
+        }
+        manager.indices = indices;
+        for (child, parent) in links
+        {
+            try!(manager.set_parent(child, Some(parent)).map_err(|e| D::Error::custom(e.to_string())));
+        }
+        let mut events = Vec::with_capacity(queue.len());
+        for (kind, entity) in queue
+        {
+            if !manager.is_valid(&entity)
+            {
+                return Err(D::Error::custom(format!("queued {:?} is not alive", entity)));
+            }
+            match Event::from_kind(kind, entity)
+            {
+                Some(event) => events.push(event),
+                None => return Err(D::Error::custom(format!("unknown event kind {}", kind))),
+            }
+        }
+        manager.restore_queue(events);
+        Ok(manager)
+    }
+}
+
+impl<T: ComponentManager> Clone for EntityManager<T>
+{
+    fn clone(&self) -> EntityManager<T>
+    {
+        let mut entities = VecMap::new();
+        for (index, entity) in self.entities.iter()
+        {
+            entities.insert(index, entity.__clone());
+        }
+        EntityManager
+        {
+            indices: self.indices.clone(),
+            entities: entities,
+            removing: self.removing.clone(),
+            event_queue: self.event_queue.clone(),
+            parents: self.parents.clone(),
+            children: self.children.clone(),
+            relations: self.relations.iter().map(|(&id, relations)| (id, relations.box_clone())).collect(),
+        }
     }
 }
 
@@ -196,9 +424,12 @@ impl<T: ComponentManager> EntityManager<T>
         EntityManager
         {
             indices: IndexPool::new(),
-            entities: HashMap::new(),
-            next_id: 0,
+            entities: VecMap::new(),
+            removing: VecMap::new(),
             event_queue: Vec::new(),
+            parents: VecMap::new(),
+            children: VecMap::new(),
+            relations: HashMap::new(),
         }
     }
 
@@ -208,18 +439,21 @@ impl<T: ComponentManager> EntityManager<T>
         let queue = ::std::mem::replace(&mut self.event_queue, Vec::new());
         for e in queue {
             match e {
-                Event::BuildEntity(entity) => s.__activated(
-                    EntityData(self.indexed(&entity)),
-                    c,
-                    m
-                ),
+                Event::BuildEntity(entity) => if let Some(indexed) = self.get(&entity) {
+                    s.__activated(EntityData(indexed), c, m);
+                },
                 Event::RemoveEntity(entity) => {
-                    {
-                        let indexed = self.indexed(&entity);
-                        s.__deactivated(EntityData(indexed), c, m);
-                        c.__remove_all(indexed);
+                    // Descendants go first, so no entity outlives its parent.
+                    let mut doomed = vec![entity];
+                    doomed.extend(self.descendants(&entity));
+                    for entity in doomed.into_iter().rev() {
+                        if let Some(indexed) = self.get(&entity) {
+                            s.__deactivated(EntityData(indexed), c, m);
+                            c.__remove_all(indexed);
+                        }
+                        self.removing.remove(&entity.index());
+                        self.remove(&entity);
                     }
-                    self.remove(&entity);
                 }
             }
         }
@@ -233,14 +467,272 @@ impl<T: ComponentManager> EntityManager<T>
         entity
     }
 
+    /// Moves the entities of another manager, their components and their relations into this
+    /// one.
+    pub fn merge(&mut self, other: EntityManager<T>, c: &mut T, other_c: T) -> EntityMap
+    {
+        let mut merge = __Merge { indices: VecMap::new(), map: EntityMap::new() };
+        let mut created = Vec::with_capacity(other.entities.len());
+        for entity in other.entities.values()
+        {
+            let new = self.create();
+            merge.map.insert(entity.1, new);
+            merge.indices.insert(entity.0, self.indexed(&new).__clone());
+            created.push(new);
+        }
+        c.__merge(other_c, &merge);
+
+        for (index, children) in other.children.iter()
+        {
+            let parent = merge.map.map(other.entities[index].1);
+            for child in children
+            {
+                let _ = self.set_parent(merge.map.map(*child), Some(parent));
+            }
+        }
+        for relations in other.relations.values()
+        {
+            relations.merge_into(&merge.map, &mut self.relations);
+        }
+        for entity in created
+        {
+            self.event_queue.push(Event::BuildEntity(entity));
+        }
+        for event in &other.event_queue
+        {
+            if let Event::RemoveEntity(entity) = *event
+            {
+                self.remove_entity(merge.map.map(entity));
+            }
+        }
+        merge.map
+    }
+
+    /// Takes on the entities, hierarchy and queue of another manager, keeping this one's
+    /// relations for the entities that are still alive.
+    #[doc(hidden)]
+    pub fn __patch(&mut self, other: EntityManager<T>)
+    {
+        for entity in self.entities.values()
+        {
+            if !other.is_valid(entity)
+            {
+                for relations in self.relations.values_mut()
+                {
+                    relations.remove_entity(entity);
+                }
+            }
+        }
+        self.indices = other.indices;
+        self.entities = other.entities;
+        self.removing = other.removing;
+        self.event_queue = other.event_queue;
+        self.parents = other.parents;
+        self.children = other.children;
+    }
+
+    /// Returns the entities that systems have already been told about.
+    #[doc(hidden)]
+    pub fn __activated(&self) -> Vec<Entity>
+    {
+        let mut building = VecMap::new();
+        for event in &self.event_queue
+        {
+            if let Event::BuildEntity(entity) = *event
+            {
+                building.insert(entity.index(), ());
+            }
+        }
+        self.entities.values().map(|e| e.1).filter(|e| !building.contains_key(&e.index())).collect()
+    }
+
+    // Puts back the events of a saved manager. Entities that were queued for removal are marked
+    // again, along with their descendants.
+    #[cfg(any(feature="serialisation", feature="serde"))]
+    fn restore_queue(&mut self, queue: Vec<Event>)
+    {
+        for event in queue
+        {
+            if let Event::RemoveEntity(entity) = event
+            {
+                let descendants: Vec<Entity> = self.descendants(&entity).collect();
+                for descendant in descendants
+                {
+                    self.removing.insert(descendant.index(), ());
+                }
+                self.removing.insert(entity.index(), ());
+            }
+            self.event_queue.push(event);
+        }
+    }
+
+    /// Creates a new entity with clones of another entity's components.
+    pub fn clone_entity(&mut self, entity: &Entity, c: &mut T) -> EcsResult<Entity>
+    {
+        let from = try!(self.try_indexed(entity)).__clone();
+        let clone = self.create();
+        c.__clone_entity(&from, self.indexed(&clone));
+        self.event_queue.push(Event::BuildEntity(clone));
+        Ok(clone)
+    }
+
+    /// Queues an entity for removal, ignoring entities that are already gone or queued.
     pub fn remove_entity(&mut self, entity: Entity)
     {
+        let _ = self.try_remove_entity(entity);
+    }
+
+    /// Queues an entity and all its descendants for removal.
+    pub fn try_remove_entity(&mut self, entity: Entity) -> EcsResult<()>
+    {
+        try!(self.try_indexed(&entity));
+        let descendants: Vec<Entity> = self.descendants(&entity).collect();
+        for descendant in descendants
+        {
+            self.removing.insert(descendant.index(), ());
+        }
+        self.removing.insert(entity.index(), ());
         self.event_queue.push(Event::RemoveEntity(entity));
+        Ok(())
+    }
+
+    /// Attaches an entity to a new parent, or detaches it with `None`.
+    ///
+    /// Fails with `EcsError::HierarchyCycle` if the parent is the entity itself or one of
+    /// its descendants.
+    pub fn set_parent(&mut self, child: Entity, parent: Option<Entity>) -> EcsResult<()>
+    {
+        try!(self.try_indexed(&child));
+        if let Some(parent) = parent
+        {
+            try!(self.try_indexed(&parent));
+            let mut ancestor = Some(parent);
+            while let Some(entity) = ancestor
+            {
+                if entity == child
+                {
+                    return Err(EcsError::HierarchyCycle(child));
+                }
+                ancestor = self.parents.get(&entity.index()).cloned();
+            }
+        }
+
+        self.detach(&child);
+        if let Some(parent) = parent
+        {
+            self.parents.insert(child.index(), parent);
+            if !self.children.contains_key(&parent.index())
+            {
+                self.children.insert(parent.index(), Vec::new());
+            }
+            self.children[parent.index()].push(child);
+        }
+        Ok(())
+    }
+
+    /// Returns the entity's parent, if it has one.
+    pub fn parent(&self, entity: &Entity) -> Option<Entity>
+    {
+        if self.is_valid(entity) { self.parents.get(&entity.index()).cloned() } else { None }
+    }
+
+    /// Returns the entity's children, in the order they were attached.
+    pub fn children(&self, entity: &Entity) -> &[Entity]
+    {
+        match self.children.get(&entity.index())
+        {
+            Some(children) if self.is_valid(entity) => children,
+            _ => &[],
+        }
+    }
+
+    /// Iterates depth-first over the entity's children, their children, and so on.
+    pub fn descendants(&self, entity: &Entity) -> Descendants<T>
+    {
+        Descendants
+        {
+            manager: self,
+            stack: self.children(entity).iter().rev().cloned().collect(),
+        }
+    }
+
+    /// Adds a relation of type `R` from the source to the target.
+    pub fn relate<R: 'static>(&mut self, source: Entity, target: Entity) -> EcsResult<bool>
+    {
+        try!(self.try_indexed(&source));
+        try!(self.try_indexed(&target));
+        Ok(self.relations_mut::<R>().insert(source, target))
+    }
+
+    /// Removes a relation of type `R`. Returns false if there was none.
+    pub fn unrelate<R: 'static>(&mut self, source: &Entity, target: &Entity) -> bool
+    {
+        self.is_valid(source) && self.is_valid(target) && self.relations_mut::<R>().remove(source, target)
+    }
+
+    /// Returns every entity the source has a relation of type `R` to.
+    pub fn targets<R: 'static>(&self, source: &Entity) -> &[Entity]
+    {
+        match self.relations::<R>()
+        {
+            Some(relations) if self.is_valid(source) => relations.targets(source),
+            _ => &[],
+        }
+    }
+
+    /// Returns every entity with a relation of type `R` to the target.
+    pub fn sources<R: 'static>(&self, target: &Entity) -> &[Entity]
+    {
+        match self.relations::<R>()
+        {
+            Some(relations) if self.is_valid(target) => relations.sources(target),
+            _ => &[],
+        }
+    }
+
+    fn relations<R: 'static>(&self) -> Option<&Relations<R>>
+    {
+        self.relations.get(&TypeId::of::<R>()).map(|r| r.as_any().downcast_ref().unwrap())
+    }
+
+    fn relations_mut<R: 'static>(&mut self) -> &mut Relations<R>
+    {
+        self.relations.entry(TypeId::of::<R>())
+            .or_insert_with(|| Box::new(Relations::<R>::new()))
+            .as_any_mut().downcast_mut().unwrap()
+    }
+
+    #[doc(hidden)]
+    pub fn __notify_relations(&mut self, events: &mut HashMap<TypeId, Box<AnyChannel>>)
+    {
+        for relations in self.relations.values_mut()
+        {
+            relations.notify(events);
+        }
+    }
+
+    fn detach(&mut self, child: &Entity)
+    {
+        if let Some(parent) = self.parents.remove(&child.index())
+        {
+            let empty = match self.children.get_mut(&parent.index())
+            {
+                Some(siblings) => {
+                    siblings.retain(|sibling| sibling != child);
+                    siblings.is_empty()
+                },
+                None => false,
+            };
+            if empty
+            {
+                self.children.remove(&parent.index());
+            }
+        }
     }
 
     pub fn iter(&self) -> EntityIter<T>
     {
-        EntityIter::Map(self.entities.values())
+        EntityIter::Indexed(self.entities.values())
     }
 
     pub fn count(&self) -> usize
@@ -250,15 +742,48 @@ impl<T: ComponentManager> EntityManager<T>
 
     pub fn indexed(&self, entity: &Entity) -> &IndexedEntity<T>
     {
-        &self.entities[entity]
+        match self.get(entity)
+        {
+            Some(indexed) => indexed,
+            None => panic!("{:?} is not a valid entity", entity),
+        }
+    }
+
+    /// Returns the indexed form of an entity that is valid and not queued for removal.
+    pub fn try_indexed(&self, entity: &Entity) -> EcsResult<&IndexedEntity<T>>
+    {
+        match self.get(entity)
+        {
+            Some(_) if self.is_pending_removal(entity) => Err(EcsError::PendingRemoval(*entity)),
+            Some(indexed) => Ok(indexed),
+            None => Err(EcsError::NoSuchEntity(*entity)),
+        }
+    }
+
+    /// Returns true if a valid entity is queued for removal.
+    #[inline]
+    pub fn is_pending_removal(&self, entity: &Entity) -> bool
+    {
+        self.is_valid(entity) && self.removing.contains_key(&entity.index())
+    }
+
+    /// Returns the indexed form of an entity, or `None` if the handle is stale.
+    #[inline]
+    pub fn get(&self, entity: &Entity) -> Option<&IndexedEntity<T>>
+    {
+        match self.entities.get(&entity.index())
+        {
+            Some(indexed) if **indexed == *entity => Some(indexed),
+            _ => None,
+        }
     }
 
     /// Creates a new `Entity`, assigning it the first available index.
     pub fn create(&mut self) -> Entity
     {
-        self.next_id += 1;
-        let ret = Entity(self.next_id);
-        self.entities.insert(ret, IndexedEntity(self.indices.get_index(), ret, PhantomData));
+        let (index, generation) = self.indices.get_index();
+        let ret = Entity(index as u32, generation);
+        self.entities.insert(index, IndexedEntity(index, ret, PhantomData));
         ret
     }
 
@@ -266,20 +791,56 @@ impl<T: ComponentManager> EntityManager<T>
     #[inline]
     pub fn is_valid(&self, entity: &Entity) -> bool
     {
-        self.entities.contains_key(entity)
+        self.get(entity).is_some()
     }
 
-    /// Deletes an entity from the manager.
+    /// Deletes an entity from the manager, detaching it from its parent and children.
     pub fn remove(&mut self, entity: &Entity)
     {
-        self.entities.remove(entity).map(|e| self.indices.return_id(e.index()));
+        if self.is_valid(entity)
+        {
+            self.detach(entity);
+            for child in self.children.remove(&entity.index()).unwrap_or(Vec::new())
+            {
+                self.parents.remove(&child.index());
+            }
+            for relations in self.relations.values_mut()
+            {
+                relations.remove_entity(entity);
+            }
+            self.entities.remove(&entity.index());
+            self.indices.return_id(entity.index());
+        }
+    }
+}
+
+pub struct Descendants<'a, T: ComponentManager + 'a>
+{
+    manager: &'a EntityManager<T>,
+    stack: Vec<Entity>,
+}
+
+impl<'a, T: ComponentManager> Iterator for Descendants<'a, T>
+{
+    type Item = Entity;
+    fn next(&mut self) -> Option<Entity>
+    {
+        let entity = match self.stack.pop()
+        {
+            Some(entity) => entity,
+            None => return None,
+        };
+        self.stack.extend(self.manager.children(&entity).iter().rev().cloned());
+        Some(entity)
     }
 }
 
+#[derive(Clone)]
 struct IndexPool
 {
     recycled: Vec<usize>,
     next_index: usize,
+    generations: Vec<Generation>,
 }
 
 // TODO: Cleanup
@@ -290,7 +851,11 @@ unsafe impl CerealData for IndexPool {
         for &idx in &self.recycled {
             try!((idx as u64).write(write));
         }
-        (self.next_index as u64).write(write)
+        try!((self.next_index as u64).write(write));
+        for &generation in &self.generations {
+            try!((generation as u64).write(write));
+        }
+        Ok(())
     }
 
     fn read(read: &mut ::std::io::Read) -> CerealResult<IndexPool> {
@@ -299,13 +864,52 @@ unsafe impl CerealData for IndexPool {
         for _ in 0..len {
             indices.push(try!(u64::read(read)) as usize);
         }
+        let next_index = try!(u64::read(read)) as usize;
+        let mut generations = Vec::with_capacity(next_index);
+        for _ in 0..next_index {
+            generations.push(try!(u64::read(read)) as Generation);
+        }
         Ok(IndexPool {
             recycled: indices,
-            next_index: try!(u64::read(read)) as usize,
+            next_index: next_index,
+            generations: generations,
         })
     }
 }
 
+#[cfg(feature="serde")]
+impl Serialize for IndexPool
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        (&self.recycled, self.next_index, &self.generations).serialize(serializer)
+    }
+}
+
+#[cfg(feature="serde")]
+impl<'de> Deserialize<'de> for IndexPool
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<IndexPool, D::Error>
+    {
+        let (recycled, next_index, generations): (Vec<usize>, usize, Vec<Generation>) =
+            try!(Deserialize::deserialize(deserializer));
+        let mut free = recycled.clone();
+        free.sort();
+        free.dedup();
+        // Generation 0 is kept for `Entity::nil()`.
+        if generations.len() != next_index || free.len() != recycled.len() || recycled.iter().any(|&i| i >= next_index)
+            || generations.contains(&0)
+        {
+            return Err(D::Error::custom("entity indices don't match their generations"));
+        }
+        Ok(IndexPool
+        {
+            recycled: recycled,
+            next_index: next_index,
+            generations: generations,
+        })
+    }
+}
 
 impl IndexPool
 {
@@ -315,6 +919,7 @@ impl IndexPool
         {
             recycled: Vec::new(),
             next_index: 0,
+            generations: Vec::new(),
         }
     }
 
@@ -323,20 +928,28 @@ impl IndexPool
         self.next_index - self.recycled.len()
     }
 
-    pub fn get_index(&mut self) -> usize
+    /// Returns a free index along with its current generation.
+    pub fn get_index(&mut self) -> (usize, Generation)
     {
         match self.recycled.pop()
         {
-            Some(id) => id,
+            Some(id) => (id, self.generations[id]),
             None => {
                 self.next_index += 1;
-                self.next_index - 1
+                self.generations.push(1);
+                (self.next_index - 1, 1)
             }
         }
     }
 
+    /// Frees an index, invalidating every handle that still refers to it.
     pub fn return_id(&mut self, id: usize)
     {
+        self.generations[id] = match self.generations[id].wrapping_add(1)
+        {
+            0 => 1,
+            generation => generation,
+        };
         self.recycled.push(id);
     }
 }
@@ -0,0 +1,61 @@
+//! Errors returned by the fallible (`try_*`) operations.
+
+use std::error::Error;
+use std::fmt;
+
+use Entity;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EcsError
+{
+    /// The entity was removed, or the handle is stale.
+    NoSuchEntity(Entity),
+    /// The entity does not have the requested component.
+    MissingComponent(Entity),
+    /// The entity is queued for removal and will be gone after the next flush.
+    PendingRemoval(Entity),
+    /// A system ordering constraint refers to a label that no system has.
+    UnknownLabel(&'static str),
+    /// The system ordering constraints form a cycle involving this system.
+    ScheduleCycle(&'static str),
+    /// A system declared access to a component list that doesn't exist.
+    UnknownComponent(&'static str),
+    /// The entity can't become a child of itself or one of its descendants.
+    HierarchyCycle(Entity),
+}
+
+pub type EcsResult<T> = Result<T, EcsError>;
+
+impl fmt::Display for EcsError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match *self
+        {
+            EcsError::NoSuchEntity(e) => write!(f, "{:?} does not exist", e),
+            EcsError::MissingComponent(e) => write!(f, "{:?} does not have the requested component", e),
+            EcsError::PendingRemoval(e) => write!(f, "{:?} is queued for removal", e),
+            EcsError::UnknownLabel(l) => write!(f, "no system is labelled `{}`", l),
+            EcsError::ScheduleCycle(s) => write!(f, "system `{}` is part of an ordering cycle", s),
+            EcsError::UnknownComponent(l) => write!(f, "no component list is named `{}`", l),
+            EcsError::HierarchyCycle(e) => write!(f, "{:?} can't be its own ancestor", e),
+        }
+    }
+}
+
+impl Error for EcsError
+{
+    fn description(&self) -> &str
+    {
+        match *self
+        {
+            EcsError::NoSuchEntity(_) => "entity does not exist",
+            EcsError::MissingComponent(_) => "entity does not have the requested component",
+            EcsError::PendingRemoval(_) => "entity is queued for removal",
+            EcsError::UnknownLabel(_) => "no system has the requested label",
+            EcsError::ScheduleCycle(_) => "system ordering constraints form a cycle",
+            EcsError::UnknownComponent(_) => "no component list has the requested name",
+            EcsError::HierarchyCycle(_) => "entity can't be its own ancestor",
+        }
+    }
+}
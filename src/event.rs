@@ -0,0 +1,160 @@
+//! Typed events sent between systems.
+//!
+//! Any system can write events to the channel for their type, and each reader keeps its own
+//! cursor so it sees every event exactly once:
+//!
+//! ```ignore
+//! data.events::<Collision>().write(Collision(a, b));
+//!
+//! // in another system, holding `reader: EventReader<Collision>`
+//! for collision in data.read_events(&mut self.reader) { ... }
+//! ```
+//!
+//! Events are kept until the second `World::update` after they were written, so a reader has
+//! to read at least once per update to not miss any.
+
+use std::any::Any;
+use std::cmp;
+use std::marker::PhantomData;
+use std::slice;
+
+pub struct EventChannel<E>
+{
+    events: Vec<E>,
+    // Id of `events[0]`. Ids keep counting up as old events are dropped.
+    start: u64,
+    // Number of events that were written before the last update.
+    previous: usize,
+}
+
+/// A position in an `EventChannel`.
+pub struct EventReader<E>
+{
+    cursor: u64,
+    _marker: PhantomData<fn(E)>,
+}
+
+impl<E> EventReader<E>
+{
+    /// Creates a reader that will see every event still in the channel.
+    pub fn new() -> EventReader<E>
+    {
+        EventReader
+        {
+            cursor: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<E> Default for EventReader<E>
+{
+    fn default() -> EventReader<E>
+    {
+        EventReader::new()
+    }
+}
+
+impl<E> EventChannel<E>
+{
+    pub fn new() -> EventChannel<E>
+    {
+        EventChannel
+        {
+            events: Vec::new(),
+            start: 0,
+            previous: 0,
+        }
+    }
+
+    pub fn write(&mut self, event: E)
+    {
+        self.events.push(event);
+    }
+
+    /// Creates a reader that will only see events written from now on.
+    pub fn reader(&self) -> EventReader<E>
+    {
+        EventReader
+        {
+            cursor: self.end(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the events the reader hasn't seen yet, and moves it past them.
+    ///
+    /// A reader that is ahead of the channel, such as one used on another world's channel
+    /// before, sees nothing this time and catches up with the end.
+    pub fn read(&self, reader: &mut EventReader<E>) -> EventIter<E>
+    {
+        let first = cmp::min(cmp::max(reader.cursor, self.start), self.end());
+        reader.cursor = self.end();
+        EventIter(self.events[(first - self.start) as usize..].iter())
+    }
+
+    /// Returns the number of events in the channel.
+    pub fn len(&self) -> usize
+    {
+        self.events.len()
+    }
+
+    /// Drops the events written before the previous update.
+    pub fn maintain(&mut self)
+    {
+        self.events.drain(..self.previous);
+        self.start += self.previous as u64;
+        self.previous = self.events.len();
+    }
+
+    fn end(&self) -> u64
+    {
+        self.start + self.events.len() as u64
+    }
+}
+
+pub struct EventIter<'a, E: 'a>(slice::Iter<'a, E>);
+
+impl<'a, E> EventIter<'a, E>
+{
+    /// An iterator over no events.
+    pub fn empty() -> EventIter<'a, E>
+    {
+        EventIter([].iter())
+    }
+}
+
+impl<'a, E> Iterator for EventIter<'a, E>
+{
+    type Item = &'a E;
+    fn next(&mut self) -> Option<&'a E>
+    {
+        self.0.next()
+    }
+}
+
+#[doc(hidden)]
+pub trait AnyChannel: Any + Send
+{
+    fn maintain(&mut self);
+    fn as_any(&self) -> &Any;
+    fn as_any_mut(&mut self) -> &mut Any;
+}
+
+impl<E: Send + 'static> AnyChannel for EventChannel<E>
+{
+    fn maintain(&mut self)
+    {
+        EventChannel::maintain(self);
+    }
+
+    fn as_any(&self) -> &Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut Any
+    {
+        self
+    }
+}
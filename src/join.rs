@@ -0,0 +1,108 @@
+//! Iterating over several component lists at once.
+//!
+//! ```ignore
+//! let c = &mut data.components;
+//! for (position, velocity) in (&mut c.position, &c.velocity).join() {
+//!     position.x += velocity.dx;
+//! }
+//! ```
+//!
+//! Only the components of entities that are present in every list are visited. The smallest
+//! list decides which entities are looked up in the others.
+
+use std::vec;
+
+/// A component list that can take part in a join, either borrowed immutably or mutably.
+pub trait Joinable
+{
+    type Item;
+
+    #[doc(hidden)]
+    fn __len(&self) -> usize;
+    #[doc(hidden)]
+    fn __indices(&self) -> Vec<usize>;
+    #[doc(hidden)]
+    fn __contains(&self, index: usize) -> bool;
+    /// Fetching the same index more than once is undefined behaviour for mutable lists.
+    #[doc(hidden)]
+    unsafe fn __fetch(&mut self, index: usize) -> Self::Item;
+}
+
+/// A tuple of component lists that can be iterated together.
+pub trait Join: Sized
+{
+    type Item;
+    /// Iterates over the components of every entity found in all the lists.
+    fn join(self) -> JoinIter<Self>;
+
+    /// The indices must be unique, or mutable lists hand out aliasing references.
+    #[doc(hidden)]
+    unsafe fn __next(&mut self, &mut vec::IntoIter<usize>) -> Option<Self::Item>;
+}
+
+pub struct JoinIter<J: Join>
+{
+    lists: J,
+    indices: vec::IntoIter<usize>,
+}
+
+impl<J: Join> Iterator for JoinIter<J>
+{
+    type Item = J::Item;
+    fn next(&mut self) -> Option<J::Item>
+    {
+        // The indices were taken from a single list by `join`, so each one appears once.
+        unsafe { self.lists.__next(&mut self.indices) }
+    }
+}
+
+macro_rules! impl_join {
+    ($($list:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($list: Joinable),+> Join for ($($list,)+)
+        {
+            type Item = ($($list::Item,)+);
+
+            fn join(self) -> JoinIter<($($list,)+)>
+            {
+                let indices = {
+                    let &($(ref $list,)+) = &self;
+                    let smallest = [$($list.__len()),+].iter().min().cloned().unwrap();
+                    let mut indices = None;
+                    $(
+                        if indices.is_none() && $list.__len() == smallest
+                        {
+                            indices = Some($list.__indices());
+                        }
+                    )+
+                    indices.unwrap()
+                };
+                JoinIter
+                {
+                    lists: self,
+                    indices: indices.into_iter(),
+                }
+            }
+
+            unsafe fn __next(&mut self, indices: &mut vec::IntoIter<usize>) -> Option<($($list::Item,)+)>
+            {
+                let &mut ($(ref mut $list,)+) = self;
+                for index in indices
+                {
+                    if $($list.__contains(index))&&+
+                    {
+                        return Some(($($list.__fetch(index),)+));
+                    }
+                }
+                None
+            }
+        }
+    };
+}
+
+impl_join!(A);
+impl_join!(A, B);
+impl_join!(A, B, C);
+impl_join!(A, B, C, D);
+impl_join!(A, B, C, D, E);
+impl_join!(A, B, C, D, E, F);
@@ -32,19 +32,49 @@
 #[macro_use]
 extern crate cereal;
 extern crate vec_map;
+#[cfg(feature="parallel")]
+extern crate rayon;
+#[cfg(feature="serde")]
+extern crate serde;
+#[cfg(feature="serde")]
+extern crate serde_value;
 
 pub use aspect::Aspect;
-pub use component::{Component, ComponentList};
+pub use command::Commands;
+pub use component::{Component, ComponentList, Changes, Tick};
 pub use component::{EntityBuilder, EntityModifier};
-pub use entity::{Entity, IndexedEntity, EntityIter};
+pub use entity::{Descendants, Entity, IndexedEntity, EntityIter};
+pub use error::{EcsError, EcsResult};
+pub use event::{EventChannel, EventReader};
+pub use join::Join;
+pub use map::{EntityMap, MapEntities};
+pub use prefab::Prefab;
+pub use relation::{Relations, Unlinked};
+pub use storage::Storage;
 pub use system::{System, Process};
-pub use world::{ComponentManager, ServiceManager, SystemManager, DataHelper, World};
+pub use world::{ComponentManager, ServiceManager, SystemManager, DataHelper, Snapshot, World};
 
 use std::ops::Deref;
 
 pub mod aspect;
+pub mod command;
 pub mod component;
+#[cfg(feature="serde")]
+pub mod delta;
+#[cfg(feature="serde")]
+pub mod dump;
 pub mod entity;
+pub mod error;
+pub mod event;
+pub mod join;
+pub mod map;
+pub mod prefab;
+pub mod relation;
+#[cfg(feature="serde")]
+pub mod replication;
+#[cfg(feature="serde")]
+pub mod save;
+pub mod storage;
 pub mod system;
 pub mod world;
 
@@ -106,20 +136,65 @@ mod macros
                     $Name
                 }
 
+                fn __lists() -> &'static [&'static str]
+                {
+                    &[]
+                }
+
                 fn __remove_all(&mut self, _: &$crate::IndexedEntity<$Name>)
                 {
 
                 }
+
+                fn __clone_entity(&mut self, _: &$crate::IndexedEntity<$Name>, _: &$crate::IndexedEntity<$Name>)
+                {
+
+                }
+
+                fn __loaded(&mut self, _: &$crate::IndexedEntity<$Name>)
+                {
+
+                }
+
+                fn __rewind(&mut self, _: &$Name)
+                {
+
+                }
+
+                fn __merge(&mut self, _: $Name, _: &$crate::map::__Merge<$Name>)
+                {
+
+                }
+
+                fn __remove_from(&mut self, _: &str, _: &$crate::IndexedEntity<$Name>)
+                {
+
+                }
+
+                fn __patch(&mut self, _: &str, _: &mut $Name, _: &$crate::map::__Merge<$Name>)
+                {
+
+                }
+
+                fn __split<'a>(&'a mut self, _: &mut $crate::system::parallel::Split<'a>)
+                {
+
+                }
+
+                fn __maintain(&mut self)
+                {
+
+                }
             }
         };
         {
             #[builder($Builder:ident)]
             $(#[$attr:meta])*
             struct $Name:ident {
-                $(#[$kind:ident] $field_name:ident : $field_ty:ty),+
+                $(#[$kind:ident $(($($kind_arg:tt)*))*] $field_name:ident : $field_ty:ty),+
             }
         } => {
-            components!($(#[$attr])* struct $Name { $(#[$kind] $field_name : $field_ty),+ });
+            components!($(#[$attr])* struct $Name { $(#[$kind $(($($kind_arg)*))*] $field_name : $field_ty),+ });
 
             #[derive(Default)]
             pub struct $Builder {
@@ -141,13 +216,13 @@ mod macros
         {
             $(#[$attr:meta])*
             struct $Name:ident {
-                $(#[$kind:ident] $field_name:ident : $field_ty:ty),+
+                $(#[$kind:ident $(($($kind_arg:tt)*))*] $field_name:ident : $field_ty:ty),+
             }
         } => {
             $(#[$attr])*
             pub struct $Name {
                 $(
-                    pub $field_name : $crate::ComponentList<$Name, $field_ty>,
+                    pub $field_name : $crate::ComponentList<$Name, $field_ty, __storage!($kind $(($($kind_arg)*))*, $field_ty)>,
                 )+
             }
 
@@ -157,49 +232,135 @@ mod macros
                 {
                     $Name {
                         $(
-                            $field_name : $crate::ComponentList::$kind()
+                            $field_name : $crate::ComponentList::new()
                         ),+
                     }
                 }
 
+                fn __lists() -> &'static [&'static str]
+                {
+                    &[$(stringify!($field_name)),+]
+                }
+
                 fn __remove_all(&mut self, entity: &$crate::IndexedEntity<$Name>)
                 {
                     $(
                         self.$field_name.__clear(entity)
                     );+
                 }
+
+                #[allow(unused_imports)]
+                fn __clone_entity(&mut self, from: &$crate::IndexedEntity<$Name>, to: &$crate::IndexedEntity<$Name>)
+                {
+                    use $crate::component::{__CloneComponent, __SkipComponent};
+                    $(
+                        self.$field_name.__clone_entity(from, to, |component| {
+                            (&$crate::component::__Cloner::<$field_ty>::new()).__clone_component(component)
+                        })
+                    );+
+                }
+
+                fn __loaded(&mut self, entity: &$crate::IndexedEntity<$Name>)
+                {
+                    $(
+                        self.$field_name.__loaded(entity)
+                    );+
+                }
+
+                fn __rewind(&mut self, current: &$Name)
+                {
+                    $(
+                        self.$field_name.__rewind(&current.$field_name)
+                    );+
+                }
+
+                #[allow(unused_imports)]
+                fn __merge(&mut self, mut other: $Name, merge: &$crate::map::__Merge<$Name>)
+                {
+                    use $crate::component::{__MapComponent, __SkipMapping};
+                    $(
+                        self.$field_name.__merge(&mut other.$field_name, merge, |component| {
+                            (&$crate::component::__Mapper::<$field_ty>::of(component)).__map_component(component, &merge.map)
+                        })
+                    );+
+                }
+
+                fn __remove_from(&mut self, list: &str, entity: &$crate::IndexedEntity<$Name>)
+                {
+                    $(
+                        if list == stringify!($field_name)
+                        {
+                            self.$field_name.__clear(entity);
+                        }
+                    )+
+                }
+
+                fn __patch(&mut self, list: &str, other: &mut $Name, merge: &$crate::map::__Merge<$Name>)
+                {
+                    $(
+                        if list == stringify!($field_name)
+                        {
+                            self.$field_name.__merge(&mut other.$field_name, merge, |_| ());
+                        }
+                    )+
+                }
+
+                fn __split<'a>(&'a mut self, split: &mut $crate::system::parallel::Split<'a>)
+                {
+                    $(
+                        split.list(stringify!($field_name), &mut self.$field_name)
+                    );+
+                }
+
+                fn __maintain(&mut self)
+                {
+                    $(
+                        self.$field_name.__maintain()
+                    );+
+                }
             }
         };
         {
             #[builder($Builder:ident)]
             $(#[$attr:meta])*
             struct $Name:ident {
-                $(#[$kind:ident] $field_name:ident : $field_ty:ty),+,
+                $(#[$kind:ident $(($($kind_arg:tt)*))*] $field_name:ident : $field_ty:ty),+,
             }
         } => {
             components!(
                 #[builder($Builder)]
                 $(#[$attr])*
                 struct $Name {
-                    $(#[$kind] $field_name : $field_ty),+
+                    $(#[$kind $(($($kind_arg)*))*] $field_name : $field_ty),+
                 }
             );
         };
         {
             $(#[$attr:meta])*
             struct $Name:ident {
-                $(#[$kind:ident] $field_name:ident : $field_ty:ty),+,
+                $(#[$kind:ident $(($($kind_arg:tt)*))*] $field_name:ident : $field_ty:ty),+,
             }
         } => {
             components!(
                 $(#[$attr])*
                 struct $Name {
-                    $(#[$kind] $field_name : $field_ty),+
+                    $(#[$kind $(($($kind_arg)*))*] $field_name : $field_ty),+
                 }
             );
         };
     }
 
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __storage {
+        (hot, $ty:ty) => { $crate::storage::HotStorage<$ty> };
+        (cold, $ty:ty) => { $crate::storage::ColdStorage<$ty> };
+        (dense, $ty:ty) => { $crate::storage::DenseStorage<$ty> };
+        (btree, $ty:ty) => { $crate::storage::BTreeStorage<$ty> };
+        (tag, $ty:ty) => { $crate::storage::TagStorage<$ty> };
+        (storage($($storage:tt)*), $ty:ty) => { $($storage)*<$ty> };
+    }
+
     #[macro_export]
     macro_rules! systems {
         {
@@ -243,7 +404,7 @@ mod macros
             $(#[$attr:meta])*
             struct $Name:ident<$components:ty, $services:ty> {
                 active: {
-                    $($field_name:ident : $field_ty:ty = $field_init:expr,)*
+                    $($field_name:ident : $field_ty:ty = $field_init:expr $(=> [$($constraint:ident($($label:ident),*)),*])*,)*
                 },
                 passive: {
                     $($p_field_name:ident : $p_field_ty:ty = $p_field_init:expr,)*
@@ -254,6 +415,7 @@ mod macros
             pub struct $Name {
                 $(pub $field_name : $field_ty,)*
                 $(pub $p_field_name : $p_field_ty,)*
+                __schedule: $crate::system::Schedule,
             }
 
             impl $crate::SystemManager for $Name
@@ -262,13 +424,40 @@ mod macros
                 type Services = $services;
                 fn __new() -> $Name
                 {
-                    $Name {
-                        $(
-                            $field_name : $field_init,
-                        )*
+                    match <$Name as $crate::SystemManager>::__try_new() {
+                        Ok(systems) => systems,
+                        Err(err) => panic!("{}", err),
+                    }
+                }
+
+                fn __try_new() -> $crate::EcsResult<$Name>
+                {
+                    let schedule = $crate::system::Schedule::new(vec![
                         $(
-                            $p_field_name : $p_field_init,
+                            $crate::system::SystemDesc::new(stringify!($field_name))
+                                $($(.$constraint(&[$(stringify!($label)),*]))*)*,
                         )*
+                    ]);
+                    match schedule {
+                        Ok(schedule) => {
+                            let mut systems = $Name {
+                                $(
+                                    $field_name : $field_init,
+                                )*
+                                $(
+                                    $p_field_name : $p_field_init,
+                                )*
+                                __schedule: schedule,
+                            };
+                            systems.__schedule.__set_parallel(vec![
+                                $(
+                                    $crate::Process::__parallel(&mut systems.$field_name).is_some(),
+                                )*
+                            ]);
+                            let lists = <$components as $crate::ComponentManager>::__lists();
+                            systems.__schedule.__check_lists(lists).map(|_| systems)
+                        },
+                        Err(err) => Err(err),
                     }
                 }
 
@@ -302,11 +491,22 @@ mod macros
                     )*
                 }
 
-                fn __update(&mut self, _co: &mut $crate::DataHelper<$components, $services>)
+                fn __update(&mut self, co: &mut $crate::DataHelper<$components, $services>)
                 {
-                    $(
-                        $crate::Process::process(&mut self.$field_name, _co);
-                    )*
+                    self.__schedule.run(&mut [
+                        $(
+                            &mut self.$field_name as &mut $crate::Process<Components=$components, Services=$services>,
+                        )*
+                    ], co);
+                }
+
+                fn __scheduled(&mut self) -> Option<(&$crate::system::Schedule, Vec<&mut $crate::Process<Components=$components, Services=$services>>)>
+                {
+                    Some((&self.__schedule, vec![
+                        $(
+                            &mut self.$field_name as &mut $crate::Process<Components=$components, Services=$services>,
+                        )*
+                    ]))
                 }
             }
         };
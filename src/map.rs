@@ -0,0 +1,129 @@
+//! Rewriting entity ids when entities move from one world into another.
+//!
+//! Entities merged into a world are given fresh ids, so components that refer to other entities
+//! need to be updated. Components do that by implementing `MapEntities`:
+//!
+//! ```ignore
+//! pub struct Target(Entity);
+//!
+//! impl MapEntities for Target
+//! {
+//!     fn map_entities(&mut self, map: &EntityMap)
+//!     {
+//!         self.0.map_entities(map);
+//!     }
+//! }
+//! ```
+//!
+//! `components!` finds the implementations by itself. Components without one are moved as they
+//! are. Ids of entities that didn't move along become `Entity::nil()`.
+
+use std::collections::HashMap;
+use std::collections::hash_map;
+use vec_map::VecMap;
+
+use {ComponentManager, Entity, IndexedEntity};
+
+/// A mapping from the ids entities had in one world to their ids in another.
+pub struct EntityMap
+{
+    entities: HashMap<Entity, Entity>,
+}
+
+impl EntityMap
+{
+    pub fn new() -> EntityMap
+    {
+        EntityMap
+        {
+            entities: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, from: Entity, to: Entity) -> Option<Entity>
+    {
+        self.entities.insert(from, to)
+    }
+
+    pub fn remove(&mut self, from: &Entity) -> Option<Entity>
+    {
+        self.entities.remove(from)
+    }
+
+    pub fn get(&self, from: &Entity) -> Option<Entity>
+    {
+        self.entities.get(from).cloned()
+    }
+
+    /// Returns the new id of an entity, or `Entity::nil()` if it isn't mapped.
+    ///
+    /// An unmapped id refers to an entity that didn't move over, so keeping it could alias an
+    /// unrelated entity in the other world.
+    pub fn map(&self, from: Entity) -> Entity
+    {
+        self.get(&from).unwrap_or(Entity::nil())
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.entities.len()
+    }
+
+    /// Iterates over (old, new) pairs, in no particular order.
+    pub fn iter(&self) -> hash_map::Iter<Entity, Entity>
+    {
+        self.entities.iter()
+    }
+}
+
+/// Something holding entity ids that need to be rewritten when it moves between worlds.
+pub trait MapEntities
+{
+    fn map_entities(&mut self, map: &EntityMap);
+}
+
+impl MapEntities for Entity
+{
+    fn map_entities(&mut self, map: &EntityMap)
+    {
+        *self = map.map(*self);
+    }
+}
+
+impl<T: MapEntities> MapEntities for Option<T>
+{
+    fn map_entities(&mut self, map: &EntityMap)
+    {
+        if let Some(ref mut inner) = *self
+        {
+            inner.map_entities(map);
+        }
+    }
+}
+
+impl<T: MapEntities> MapEntities for Vec<T>
+{
+    fn map_entities(&mut self, map: &EntityMap)
+    {
+        for inner in self
+        {
+            inner.map_entities(map);
+        }
+    }
+}
+
+impl<T: MapEntities> MapEntities for Box<T>
+{
+    fn map_entities(&mut self, map: &EntityMap)
+    {
+        (**self).map_entities(map);
+    }
+}
+
+#[doc(hidden)]
+pub struct __Merge<C: ComponentManager>
+{
+    // Index in the merged world -> entity in this one.
+    pub indices: VecMap<IndexedEntity<C>>,
+    pub map: EntityMap,
+}
@@ -0,0 +1,81 @@
+//! Reusable templates for spawning entities.
+
+use BuildData;
+use {Component, ComponentList, ComponentManager};
+use Storage;
+
+/// A named set of component values, and child prefabs, that can be spawned many times.
+///
+/// eg:
+///
+/// ```ignore
+/// let turret = Prefab::new("turret")
+///     .with(|c| &mut c.health, 50)
+///     .child(Prefab::new("barrel").with(|c| &mut c.damage, 5));
+/// let entity = data.spawn_prefab(&turret, |e: BuildData<MyComponents>, c: &mut MyComponents| {
+///     c.position.add(&e, spawn_point);
+/// });
+/// ```
+pub struct Prefab<C: ComponentManager>
+{
+    name: String,
+    parts: Vec<Box<Fn(&BuildData<C>, &mut C)>>,
+    children: Vec<Prefab<C>>,
+}
+
+impl<C: ComponentManager> Prefab<C>
+{
+    pub fn new<N: Into<String>>(name: N) -> Prefab<C>
+    {
+        Prefab
+        {
+            name: name.into(),
+            parts: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str
+    {
+        &self.name
+    }
+
+    /// Gives every spawned entity a clone of the component.
+    pub fn with<T, S, F>(mut self, list: F, component: T) -> Prefab<C>
+        where T: Component + Clone, S: Storage<T>, F: Fn(&mut C) -> &mut ComponentList<C, T, S> + 'static
+    {
+        self.parts.push(Box::new(move |e, c| { list(c).add(e, component.clone()); }));
+        self
+    }
+
+    /// Spawns the child prefab along with every instance, as a child entity of it.
+    pub fn child(mut self, prefab: Prefab<C>) -> Prefab<C>
+    {
+        self.children.push(prefab);
+        self
+    }
+
+    pub fn children(&self) -> &[Prefab<C>]
+    {
+        &self.children
+    }
+
+    /// Finds a prefab by name among this one and its descendants.
+    pub fn find(&self, name: &str) -> Option<&Prefab<C>>
+    {
+        if self.name == name
+        {
+            return Some(self);
+        }
+        self.children.iter().filter_map(|child| child.find(name)).next()
+    }
+
+    #[doc(hidden)]
+    pub fn __build(&self, e: &BuildData<C>, c: &mut C)
+    {
+        for part in &self.parts
+        {
+            part(e, c);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Typed relations between entities.
+//!
+//! A relation is a directed edge from a source entity to a target entity, labelled by any
+//! `'static` type:
+//!
+//! ```ignore
+//! pub struct Targets;
+//!
+//! data.relate::<Targets>(turret, ship).unwrap();
+//! for turret in data.sources::<Targets>(&ship) { ... }
+//! ```
+//!
+//! Both directions are indexed, so finding every entity that targets a ship is as cheap as
+//! finding what a turret targets. When either end of an edge is removed the edge goes with it,
+//! and if the source is still alive an `Unlinked<R>` event is written so it can react.
+//!
+//! Relations are not saved along with the world, but `DataHelper::merge` carries them over.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use vec_map::VecMap;
+
+use {Entity, EntityMap};
+use event::{AnyChannel, EventChannel};
+
+/// The edges of one kind of relation.
+pub struct Relations<R>
+{
+    // Source index -> targets.
+    targets: VecMap<Vec<Entity>>,
+    // Target index -> sources.
+    sources: VecMap<Vec<Entity>>,
+    // Edges whose target was removed while the source stayed alive.
+    unlinked: Vec<Unlinked<R>>,
+}
+
+/// Event written when the target of a relation is removed.
+pub struct Unlinked<R>
+{
+    pub source: Entity,
+    pub target: Entity,
+    _marker: PhantomData<fn(R)>,
+}
+
+impl<R> Clone for Relations<R>
+{
+    fn clone(&self) -> Relations<R>
+    {
+        Relations
+        {
+            targets: self.targets.clone(),
+            sources: self.sources.clone(),
+            unlinked: self.unlinked.clone(),
+        }
+    }
+}
+
+impl<R> Clone for Unlinked<R>
+{
+    fn clone(&self) -> Unlinked<R>
+    {
+        Unlinked { source: self.source, target: self.target, _marker: PhantomData }
+    }
+}
+
+impl<R> Relations<R>
+{
+    pub fn new() -> Relations<R>
+    {
+        Relations
+        {
+            targets: VecMap::new(),
+            sources: VecMap::new(),
+            unlinked: Vec::new(),
+        }
+    }
+
+    /// Adds an edge. Returns false if it already existed.
+    pub fn insert(&mut self, source: Entity, target: Entity) -> bool
+    {
+        if self.contains(&source, &target)
+        {
+            return false;
+        }
+        push(&mut self.targets, source.index(), target);
+        push(&mut self.sources, target.index(), source);
+        true
+    }
+
+    /// Removes an edge. Returns false if there was none.
+    pub fn remove(&mut self, source: &Entity, target: &Entity) -> bool
+    {
+        if !self.contains(source, target)
+        {
+            return false;
+        }
+        pull(&mut self.targets, source.index(), target);
+        pull(&mut self.sources, target.index(), source);
+        true
+    }
+
+    pub fn contains(&self, source: &Entity, target: &Entity) -> bool
+    {
+        self.targets(source).contains(target)
+    }
+
+    /// Returns the entities the source is related to, in the order the edges were added.
+    pub fn targets(&self, source: &Entity) -> &[Entity]
+    {
+        find(&self.targets, source)
+    }
+
+    /// Returns the entities related to the target, in the order the edges were added.
+    pub fn sources(&self, target: &Entity) -> &[Entity]
+    {
+        find(&self.sources, target)
+    }
+
+    fn remove_entity(&mut self, entity: &Entity)
+    {
+        for target in self.targets.remove(&entity.index()).unwrap_or(Vec::new())
+        {
+            pull(&mut self.sources, target.index(), entity);
+        }
+        for source in self.sources.remove(&entity.index()).unwrap_or(Vec::new())
+        {
+            pull(&mut self.targets, source.index(), entity);
+            if source != *entity
+            {
+                self.unlinked.push(Unlinked { source: source, target: *entity, _marker: PhantomData });
+            }
+        }
+    }
+}
+
+fn find<'a>(edges: &'a VecMap<Vec<Entity>>, entity: &Entity) -> &'a [Entity]
+{
+    match edges.get(&entity.index())
+    {
+        Some(list) => list,
+        None => &[],
+    }
+}
+
+fn push(edges: &mut VecMap<Vec<Entity>>, index: usize, entity: Entity)
+{
+    if !edges.contains_key(&index)
+    {
+        edges.insert(index, Vec::new());
+    }
+    edges[index].push(entity);
+}
+
+fn pull(edges: &mut VecMap<Vec<Entity>>, index: usize, entity: &Entity)
+{
+    let empty = match edges.get_mut(&index)
+    {
+        Some(list) => {
+            list.retain(|e| e != entity);
+            list.is_empty()
+        },
+        None => false,
+    };
+    if empty
+    {
+        edges.remove(&index);
+    }
+}
+
+#[doc(hidden)]
+pub trait AnyRelations: Send + Sync
+{
+    fn remove_entity(&mut self, &Entity);
+    /// Adds the edges to another set of relations, with both ends mapped to their new ids.
+    fn merge_into(&self, &EntityMap, &mut HashMap<TypeId, Box<AnyRelations>>);
+    /// Moves the pending `Unlinked` notifications into their event channel.
+    fn notify(&mut self, &mut HashMap<TypeId, Box<AnyChannel>>);
+    fn box_clone(&self) -> Box<AnyRelations>;
+    fn as_any(&self) -> &Any;
+    fn as_any_mut(&mut self) -> &mut Any;
+}
+
+impl<R: 'static> AnyRelations for Relations<R>
+{
+    fn remove_entity(&mut self, entity: &Entity)
+    {
+        Relations::remove_entity(self, entity);
+    }
+
+    fn merge_into(&self, map: &EntityMap, into: &mut HashMap<TypeId, Box<AnyRelations>>)
+    {
+        let relations = into.entry(TypeId::of::<R>())
+            .or_insert_with(|| Box::new(Relations::<R>::new()))
+            .as_any_mut().downcast_mut::<Relations<R>>().unwrap();
+        for (index, targets) in self.targets.iter()
+        {
+            for target in targets
+            {
+                // Only the target's list knows the source's generation.
+                let source = *self.sources(target).iter().find(|s| s.index() == index).unwrap();
+                let (source, target) = (map.map(source), map.map(*target));
+                if source != Entity::nil() && target != Entity::nil()
+                {
+                    relations.insert(source, target);
+                }
+            }
+        }
+    }
+
+    fn notify(&mut self, events: &mut HashMap<TypeId, Box<AnyChannel>>)
+    {
+        if self.unlinked.is_empty()
+        {
+            return;
+        }
+        let channel = events.entry(TypeId::of::<Unlinked<R>>())
+            .or_insert_with(|| Box::new(EventChannel::<Unlinked<R>>::new()))
+            .as_any_mut().downcast_mut::<EventChannel<Unlinked<R>>>().unwrap();
+        for unlinked in self.unlinked.drain(..)
+        {
+            channel.write(unlinked);
+        }
+    }
+
+    fn box_clone(&self) -> Box<AnyRelations>
+    {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut Any
+    {
+        self
+    }
+}
@@ -0,0 +1,346 @@
+//! Keeping copies of a world on clients in step with the server's.
+//!
+//! The server names the component lists it replicates, and makes a `Packet` for each client
+//! with the entities spawned and despawned and the components that changed since that client's
+//! last packet. The client applies packets to its own world, where the entities have their own
+//! ids:
+//!
+//! ```ignore
+//! let mut server = Server::new().replicate("position").replicate("sprite");
+//! let id = server.connect();
+//! let bytes = serde_json::to_vec(&server.update(&world, id)?)?;
+//!
+//! // on the client, after receiving the bytes:
+//! client.apply(&mut local_world, serde_json::from_slice(&bytes)?)?;
+//! let ship = client.local(server_ship);
+//! ```
+//!
+//! Packets don't care how they get to the client, but they hold components as `Value`s, so they
+//! must be sent in a self-describing format such as JSON. Each packet builds on the last one
+//! sent to the same client, so they need to arrive in order. Components implementing
+//! `MapEntities` have server ids rewritten to the client's, and ids of entities the client
+//! hasn't been sent become `Entity::nil()`.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::DeserializeOwned;
+use serde_value::{self, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::mem;
+
+use {ComponentManager, DataHelper, Entity, EntityMap, SystemManager, World};
+use delta::{ComponentDelta, Delta};
+use entity::Generation;
+
+/// Identifies a client connected to a `Server`.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ClientId(usize);
+
+/// The changes a client needs to catch up with the server's world.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet
+{
+    spawned: Vec<Entity>,
+    despawned: Vec<Entity>,
+    components: BTreeMap<String, ComponentDelta>,
+}
+
+impl Packet
+{
+    /// Returns the server's ids of the entities to create.
+    pub fn spawned(&self) -> &[Entity]
+    {
+        &self.spawned
+    }
+
+    /// Returns the server's ids of the entities to remove.
+    pub fn despawned(&self) -> &[Entity]
+    {
+        &self.despawned
+    }
+
+    /// Returns the changes to a replicated component list, by the server's entity indices.
+    pub fn components(&self, list: &str) -> Option<&ComponentDelta>
+    {
+        self.components.get(list)
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.spawned.is_empty() && self.despawned.is_empty() && self.components.is_empty()
+    }
+}
+
+/// Makes packets from the server's world, remembering what each client has been sent.
+pub struct Server
+{
+    lists: Vec<String>,
+    // The state last sent to each client, or None if it hasn't been sent anything.
+    clients: HashMap<ClientId, Option<Value>>,
+    next_client: usize,
+}
+
+impl Server
+{
+    pub fn new() -> Server
+    {
+        Server
+        {
+            lists: Vec::new(),
+            clients: HashMap::new(),
+            next_client: 0,
+        }
+    }
+
+    /// Replicates a component list, named after its field in `components!`.
+    pub fn replicate(mut self, list: &str) -> Server
+    {
+        self.lists.push(list.to_string());
+        self
+    }
+
+    /// Adds a client. Its first packet holds every replicated entity.
+    pub fn connect(&mut self) -> ClientId
+    {
+        let id = ClientId(self.next_client);
+        self.next_client += 1;
+        self.clients.insert(id, None);
+        id
+    }
+
+    pub fn disconnect(&mut self, client: ClientId)
+    {
+        self.clients.remove(&client);
+    }
+
+    /// Makes the next packet for a client.
+    ///
+    /// Entities waiting to be built by the next flush are left for a later packet.
+    pub fn update<S: SystemManager>(&mut self, world: &World<S>, client: ClientId) -> Result<Packet, String>
+        where DataHelper<S::Components, S::Services>: Serialize
+    {
+        let current = try!(self.state(&world.data));
+        let last = match self.clients.get_mut(&client)
+        {
+            Some(last) => mem::replace(last, Some(current.clone())),
+            None => return Err(format!("{:?} is not connected", client)),
+        };
+        let last = match last
+        {
+            Some(last) => last,
+            None => try!(self.empty()),
+        };
+        let delta = try!(Delta::between(&last, &current));
+        let mut components = BTreeMap::new();
+        for name in &self.lists
+        {
+            if let Some(list) = delta.components(name)
+            {
+                components.insert(name.clone(), list.clone());
+            }
+        }
+        Ok(Packet
+        {
+            spawned: delta.created().to_vec(),
+            despawned: delta.removed().to_vec(),
+            components: components,
+        })
+    }
+
+    // Saves the world like a `DataHelper`, keeping only the active entities and the replicated
+    // lists, so `Delta` can compare it.
+    fn state<T: Serialize>(&self, data: &T) -> Result<Value, String>
+    {
+        let (_, (_, live, _, queue), mut lists): (Value, (Value, Vec<Entity>, Value, Vec<(u8, Entity)>), BTreeMap<String, Value>) =
+            try!(decode(try!(encode(data))));
+        let building: HashSet<Entity> = queue.into_iter().filter(|&(kind, _)| kind == 0).map(|(_, e)| e).collect();
+        let live: Vec<Entity> = live.into_iter().filter(|e| !building.contains(e)).collect();
+        let indices: HashSet<usize> = live.iter().map(|e| e.index()).collect();
+        let mut replicated = BTreeMap::new();
+        for name in &self.lists
+        {
+            let list = match lists.remove(name)
+            {
+                Some(list) => list,
+                None => return Err(format!("unknown component {:?}", name)),
+            };
+            let entries: Vec<(usize, Value)> = try!(decode(list));
+            replicated.insert(name.clone(), entries.into_iter().filter(|&(i, _)| indices.contains(&i)).collect::<Vec<_>>());
+        }
+        layout(live, replicated)
+    }
+
+    fn empty(&self) -> Result<Value, String>
+    {
+        layout(Vec::new(), self.lists.iter().map(|name| (name.clone(), Vec::new())).collect())
+    }
+}
+
+fn layout(live: Vec<Entity>, lists: BTreeMap<String, Vec<(usize, Value)>>) -> Result<Value, String>
+{
+    let indices: (Vec<usize>, usize, Vec<Generation>) = (Vec::new(), 0, Vec::new());
+    let links: Vec<(Entity, Entity)> = Vec::new();
+    let queue: Vec<(u8, Entity)> = Vec::new();
+    encode(&((), (indices, live, links, queue), lists))
+}
+
+/// Applies packets from a server to a local world.
+pub struct Client
+{
+    // Server id -> local id.
+    entities: EntityMap,
+    // Server index -> server id.
+    indices: HashMap<usize, Entity>,
+}
+
+impl Client
+{
+    pub fn new() -> Client
+    {
+        Client
+        {
+            entities: EntityMap::new(),
+            indices: HashMap::new(),
+        }
+    }
+
+    /// Returns the local id of an entity on the server.
+    pub fn local(&self, server: Entity) -> Option<Entity>
+    {
+        self.entities.get(&server)
+    }
+
+    /// Returns the mapping from server ids to local ones.
+    pub fn entities(&self) -> &EntityMap
+    {
+        &self.entities
+    }
+
+    /// Creates, removes and updates entities in the local world to match the packet.
+    ///
+    /// Created and removed entities are queued for the next flush, like any others. Entities
+    /// removed from the local world are forgotten, and the server's updates to them ignored.
+    pub fn apply<S: SystemManager>(&mut self, world: &mut World<S>, packet: Packet) -> Result<(), String>
+        where S::Components: Serialize + DeserializeOwned
+    {
+        let mut forgotten = HashSet::new();
+        for (&index, server) in &self.indices
+        {
+            if !self.entities.get(server).map_or(false, |local| world.is_valid(&local))
+            {
+                forgotten.insert(index);
+            }
+        }
+        for index in &forgotten
+        {
+            if let Some(server) = self.indices.remove(index)
+            {
+                self.entities.remove(&server);
+            }
+        }
+
+        for server in packet.despawned
+        {
+            if self.indices.get(&server.index()) == Some(&server)
+            {
+                self.indices.remove(&server.index());
+            }
+            if let Some(local) = self.entities.remove(&server)
+            {
+                world.remove_entity(local);
+            }
+        }
+        let mut spawned = HashSet::new();
+        for server in packet.spawned
+        {
+            let local = world.create_entity(());
+            self.entities.insert(server, local);
+            self.indices.insert(server.index(), server);
+            forgotten.remove(&server.index());
+            spawned.insert(local);
+        }
+
+        let mut touched = HashSet::new();
+        let mut lists = match try!(encode(&S::Components::__new()))
+        {
+            Value::Map(lists) => lists,
+            _ => return Err("components must be saved as a struct".to_string()),
+        };
+        let mut targets = Vec::new();
+        for (name, list) in packet.components
+        {
+            for index in list.removed
+            {
+                // Components of despawned entities are listed as removed too. When a spawned
+                // entity took over the index, it has nothing to remove yet, and gets all its
+                // components as added.
+                if let Some(local) = self.local_at(index)
+                {
+                    world.data.__remove_from(&name, &local);
+                    touched.insert(local);
+                }
+            }
+            let entries = match lists.get_mut(&Value::String(name.clone()))
+            {
+                Some(&mut Value::Seq(ref mut entries)) => entries,
+                _ => return Err(format!("unknown component {:?}", name)),
+            };
+            for (index, component) in list.added.into_iter().chain(list.changed)
+            {
+                let local = match self.local_at(index)
+                {
+                    Some(local) => local,
+                    None if forgotten.contains(&index) => continue,
+                    None => return Err(format!("no entity has server index {}", index)),
+                };
+                entries.push(Value::Seq(vec![Value::U64(index as u64), component]));
+                targets.push((index, local));
+                touched.insert(local);
+            }
+        }
+        let other: S::Components = try!(decode(Value::Map(lists)));
+        let map = mem::replace(&mut self.entities, EntityMap::new());
+        self.entities = world.data.__merge_components(other, &targets, map);
+
+        // New entities are seen by the systems when they're flushed.
+        for local in touched
+        {
+            if !spawned.contains(&local)
+            {
+                let _ = world.try_modify_entity(local, ());
+            }
+        }
+        Ok(())
+    }
+
+    fn local_at(&self, index: usize) -> Option<Entity>
+    {
+        self.indices.get(&index).and_then(|server| self.entities.get(server))
+    }
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<Value, String>
+{
+    serde_value::to_value(value).map_err(|e| e.to_string())
+}
+
+fn decode<T: DeserializeOwned>(value: Value) -> Result<T, String>
+{
+    value.deserialize_into().map_err(|e| e.to_string())
+}
+
+impl Serialize for Packet
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        (&self.spawned, &self.despawned, &self.components).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Packet
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Packet, D::Error>
+    {
+        let (spawned, despawned, components) = try!(Deserialize::deserialize(deserializer));
+        Ok(Packet { spawned: spawned, despawned: despawned, components: components })
+    }
+}
@@ -0,0 +1,280 @@
+//! Versioned saves, which can be loaded after component types have changed.
+//!
+//! A save starts with a header holding the format version and a manifest of every component
+//! list along with the version of its components. When loading, components saved at an older
+//! version are passed through the upgrades registered in the `Schema`:
+//!
+//! ```ignore
+//! let schema = Schema::new()
+//!     .version("position", 1)
+//!     .convert("position", 0, |(x, y): (f32, f32)| Position { x: x, y: y, z: 0.0 });
+//!
+//! world.save_versioned(&schema, &mut serde_json::Serializer::new(file))?;
+//! let world = World::<MySystems>::load_versioned(&schema, &mut serde_json::Deserializer::from_reader(file))?;
+//! ```
+//!
+//! Upgrades work on a generic `Value` rather than the saved bytes, so the save must be in a
+//! self-describing format such as JSON or RON.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{DeserializeOwned, Error as DeError};
+use serde::ser::Error as SerError;
+use serde_value;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+use Entity;
+use entity::Generation;
+
+pub use serde_value::Value;
+
+/// Version of the layout of saves, bumped whenever the crate changes what it writes.
+pub const FORMAT_VERSION: u32 = 2;
+
+const MAGIC: &'static str = "ecs-rs world";
+
+/// Upgrades a single component by one version.
+pub type Upgrade = Box<Fn(Value) -> Result<Value, String>>;
+
+/// The current version of each component list, and how to upgrade older components.
+///
+/// Lists are named after their field in `components!`. Lists that aren't mentioned are at
+/// version 0.
+pub struct Schema
+{
+    versions: HashMap<String, u32>,
+    upgrades: HashMap<(String, u32), Upgrade>,
+}
+
+impl Schema
+{
+    pub fn new() -> Schema
+    {
+        Schema
+        {
+            versions: HashMap::new(),
+            upgrades: HashMap::new(),
+        }
+    }
+
+    /// Sets the current version of a list's components.
+    ///
+    /// Lists with a version are created empty when loading a save made before they existed.
+    pub fn version(mut self, list: &str, version: u32) -> Schema
+    {
+        self.versions.insert(list.to_string(), version);
+        self
+    }
+
+    /// Registers an upgrade of a list's components from version `from` to `from + 1`.
+    pub fn upgrade<F>(mut self, list: &str, from: u32, upgrade: F) -> Schema
+        where F: Fn(Value) -> Result<Value, String> + 'static
+    {
+        self.upgrades.insert((list.to_string(), from), Box::new(upgrade));
+        self
+    }
+
+    /// Like `upgrade`, but converts between the old and new component types directly.
+    pub fn convert<A, B, F>(self, list: &str, from: u32, convert: F) -> Schema
+        where A: DeserializeOwned, B: Serialize, F: Fn(A) -> B + 'static
+    {
+        self.upgrade(list, from, move |value| {
+            let old = try!(value.deserialize_into().map_err(|e| e.to_string()));
+            serde_value::to_value(convert(old)).map_err(|e| e.to_string())
+        })
+    }
+
+    /// Returns the current version of a list's components.
+    pub fn version_of(&self, list: &str) -> u32
+    {
+        self.versions.get(list).cloned().unwrap_or(0)
+    }
+
+    fn migrate(&self, list: &str, saved: u32, components: Value) -> Result<Value, String>
+    {
+        let current = self.version_of(list);
+        if saved > current
+        {
+            return Err(format!("`{}` was saved at version {}, but the newest known is {}", list, saved, current));
+        }
+        if saved == current
+        {
+            return Ok(components);
+        }
+        let entries = match components
+        {
+            Value::Seq(entries) => entries,
+            _ => return Err(format!("`{}` is not a component list", list)),
+        };
+        let mut upgraded = Vec::with_capacity(entries.len());
+        for entry in entries
+        {
+            let (index, mut component) = match entry
+            {
+                Value::Seq(mut pair) => match (pair.pop(), pair.pop()) {
+                    (Some(component), Some(index)) if pair.is_empty() => (index, component),
+                    _ => return Err(format!("`{}` is not a component list", list)),
+                },
+                _ => return Err(format!("`{}` is not a component list", list)),
+            };
+            for version in saved..current
+            {
+                let upgrade = match self.upgrades.get(&(list.to_string(), version))
+                {
+                    Some(upgrade) => upgrade,
+                    None => return Err(format!("no upgrade for `{}` from version {}", list, version)),
+                };
+                component = try!(upgrade(component));
+            }
+            upgraded.push(Value::Seq(vec![index, component]));
+        }
+        Ok(Value::Seq(upgraded))
+    }
+}
+
+#[doc(hidden)]
+pub fn __save<T, S>(data: &T, schema: &Schema, serializer: S) -> Result<S::Ok, S::Error>
+    where T: Serialize, S: Serializer
+{
+    let value = try!(serde_value::to_value(data).map_err(S::Error::custom));
+    let mut manifest = Vec::new();
+    match components(&value)
+    {
+        Some(&Value::Map(ref lists)) => for name in lists.keys()
+        {
+            if let Value::String(ref name) = *name
+            {
+                manifest.push((name.clone(), schema.version_of(name)));
+            }
+        },
+        _ => return Err(S::Error::custom("components must be saved as a struct")),
+    }
+    (MAGIC, FORMAT_VERSION, manifest, value).serialize(serializer)
+}
+
+#[doc(hidden)]
+pub fn __load<'de, T, D>(schema: &Schema, deserializer: D) -> Result<T, D::Error>
+    where T: DeserializeOwned, D: Deserializer<'de>
+{
+    let (magic, format, manifest, mut value): (String, u32, Vec<(String, u32)>, Value) =
+        try!(Deserialize::deserialize(deserializer));
+    if magic != MAGIC
+    {
+        return Err(D::Error::custom("not a saved world"));
+    }
+    if format > FORMAT_VERSION
+    {
+        return Err(D::Error::custom(format!("saved in format version {}, but the newest known is {}", format, FORMAT_VERSION)));
+    }
+    if format < 2
+    {
+        // Version 1 required the queue to be flushed, so it didn't save one.
+        if let Some(&mut Value::Seq(ref mut entities)) = entities_mut(&mut value)
+        {
+            entities.push(Value::Seq(Vec::new()));
+        }
+    }
+    {
+        let lists = match components_mut(&mut value)
+        {
+            Some(&mut Value::Map(ref mut lists)) => lists,
+            _ => return Err(D::Error::custom("components must be saved as a struct")),
+        };
+        for (name, saved) in manifest
+        {
+            if let Some(list) = lists.get_mut(&Value::String(name.clone()))
+            {
+                let components = mem::replace(list, Value::Unit);
+                *list = try!(schema.migrate(&name, saved, components).map_err(D::Error::custom));
+            }
+        }
+        for name in schema.versions.keys()
+        {
+            let key = Value::String(name.clone());
+            if !lists.contains_key(&key)
+            {
+                lists.insert(key, Value::Seq(Vec::new()));
+            }
+        }
+    }
+    value.deserialize_into().map_err(D::Error::custom)
+}
+
+/// Writes the data like a normal save, keeping only the given entities and their components,
+/// and the given services in place of the world's.
+#[doc(hidden)]
+pub fn __save_entities<T, M, S>(data: &T, services: &M, keep: &HashSet<Entity>, serializer: S) -> Result<S::Ok, S::Error>
+    where T: Serialize, M: Serialize, S: Serializer
+{
+    let mut value = try!(serde_value::to_value(data).map_err(S::Error::custom));
+    match value
+    {
+        Value::Seq(ref mut parts) if parts.len() == 3 => parts[0] = try!(serde_value::to_value(services).map_err(S::Error::custom)),
+        _ => return Err(S::Error::custom("unexpected layout of saved world")),
+    }
+    {
+        let manager = match entities_mut(&mut value)
+        {
+            Some(&mut Value::Seq(ref mut manager)) if manager.len() == 4 => manager,
+            _ => return Err(S::Error::custom("unexpected layout of saved world")),
+        };
+        let (_, next_index, generations): (Vec<usize>, usize, Vec<Generation>) =
+            try!(mem::replace(&mut manager[0], Value::Unit).deserialize_into().map_err(S::Error::custom));
+        let live: Vec<Entity> = try!(mem::replace(&mut manager[1], Value::Unit).deserialize_into().map_err(S::Error::custom));
+        let links: Vec<(Entity, Entity)> = try!(mem::replace(&mut manager[2], Value::Unit).deserialize_into().map_err(S::Error::custom));
+        let queue: Vec<(u8, Entity)> = try!(mem::replace(&mut manager[3], Value::Unit).deserialize_into().map_err(S::Error::custom));
+        let live: Vec<Entity> = live.into_iter().filter(|e| keep.contains(e)).collect();
+        // Every index that isn't kept is free in the save, so loading it doesn't leak them.
+        let kept: HashSet<usize> = live.iter().map(|e| e.index()).collect();
+        let recycled: Vec<usize> = (0..next_index).filter(|i| !kept.contains(i)).collect();
+        manager[0] = try!(serde_value::to_value((recycled, next_index, generations)).map_err(S::Error::custom));
+        let links: Vec<(Entity, Entity)> = links.into_iter().filter(|&(child, parent)| keep.contains(&child) && keep.contains(&parent)).collect();
+        let queue: Vec<(u8, Entity)> = queue.into_iter().filter(|&(_, e)| keep.contains(&e)).collect();
+        manager[1] = try!(serde_value::to_value(live).map_err(S::Error::custom));
+        manager[2] = try!(serde_value::to_value(links).map_err(S::Error::custom));
+        manager[3] = try!(serde_value::to_value(queue).map_err(S::Error::custom));
+    }
+    {
+        let indices: HashSet<usize> = keep.iter().map(|e| e.index()).collect();
+        let lists = match components_mut(&mut value)
+        {
+            Some(&mut Value::Map(ref mut lists)) => lists,
+            _ => return Err(S::Error::custom("components must be saved as a struct")),
+        };
+        for list in lists.values_mut()
+        {
+            let entries: Vec<(usize, Value)> = try!(mem::replace(list, Value::Unit).deserialize_into().map_err(S::Error::custom));
+            let entries: Vec<(usize, Value)> = entries.into_iter().filter(|&(index, _)| indices.contains(&index)).collect();
+            *list = try!(serde_value::to_value(entries).map_err(S::Error::custom));
+        }
+    }
+    value.serialize(serializer)
+}
+
+// The data is saved as (services, entities, components).
+fn components(value: &Value) -> Option<&Value>
+{
+    match *value
+    {
+        Value::Seq(ref parts) if parts.len() == 3 => Some(&parts[2]),
+        _ => None,
+    }
+}
+
+fn entities_mut(value: &mut Value) -> Option<&mut Value>
+{
+    match *value
+    {
+        Value::Seq(ref mut parts) if parts.len() == 3 => Some(&mut parts[1]),
+        _ => None,
+    }
+}
+
+fn components_mut(value: &mut Value) -> Option<&mut Value>
+{
+    match *value
+    {
+        Value::Seq(ref mut parts) if parts.len() == 3 => Some(&mut parts[2]),
+        _ => None,
+    }
+}
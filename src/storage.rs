@@ -0,0 +1,253 @@
+//! Ways of storing the components in a `ComponentList`.
+//!
+//! The storage is picked per field in the `components!` macro:
+//!
+//! - `#[hot]`: `HotStorage`, a `VecMap`. Fast, but uses memory for every entity.
+//! - `#[cold]`: `ColdStorage`, a `HashMap`. Compact, but slower to access.
+//! - `#[dense]`: `DenseStorage`, a sparse set. Components are packed together, so iterating is fast.
+//! - `#[btree]`: `BTreeStorage`, a `BTreeMap`. Compact and iterated in entity order.
+//! - `#[tag]`: `TagStorage`, a bit set for zero-sized marker components.
+//! - `#[storage(MyStorage)]`: any other type implementing `Storage`, given the component type
+//!   as its only parameter.
+
+use std::collections::{BTreeMap, HashMap};
+use std::mem;
+use vec_map::VecMap;
+
+/// A container of components, addressed by entity index.
+pub trait Storage<T>: 'static
+{
+    fn new() -> Self where Self: Sized;
+    fn len(&self) -> usize;
+    fn get(&self, index: usize) -> Option<&T>;
+    fn get_mut(&mut self, index: usize) -> Option<&mut T>;
+    fn insert(&mut self, index: usize, component: T) -> Option<T>;
+    fn remove(&mut self, index: usize) -> Option<T>;
+    /// Iterates over every stored component along with its entity index.
+    fn iter<'a>(&'a self) -> Box<Iterator<Item=(usize, &'a T)> + 'a>;
+
+    fn contains(&self, index: usize) -> bool
+    {
+        self.get(index).is_some()
+    }
+}
+
+#[derive(Clone)]
+pub struct HotStorage<T>(VecMap<T>);
+
+impl<T: 'static> Storage<T> for HotStorage<T>
+{
+    fn new() -> HotStorage<T> { HotStorage(VecMap::new()) }
+    fn len(&self) -> usize { self.0.len() }
+    fn get(&self, index: usize) -> Option<&T> { self.0.get(&index) }
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> { self.0.get_mut(&index) }
+    fn insert(&mut self, index: usize, component: T) -> Option<T> { self.0.insert(index, component) }
+    fn remove(&mut self, index: usize) -> Option<T> { self.0.remove(&index) }
+    fn iter<'a>(&'a self) -> Box<Iterator<Item=(usize, &'a T)> + 'a> { Box::new(self.0.iter()) }
+    fn contains(&self, index: usize) -> bool { self.0.contains_key(&index) }
+}
+
+#[derive(Clone)]
+pub struct ColdStorage<T>(HashMap<usize, T>);
+
+impl<T: 'static> Storage<T> for ColdStorage<T>
+{
+    fn new() -> ColdStorage<T> { ColdStorage(HashMap::new()) }
+    fn len(&self) -> usize { self.0.len() }
+    fn get(&self, index: usize) -> Option<&T> { self.0.get(&index) }
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> { self.0.get_mut(&index) }
+    fn insert(&mut self, index: usize, component: T) -> Option<T> { self.0.insert(index, component) }
+    fn remove(&mut self, index: usize) -> Option<T> { self.0.remove(&index) }
+    fn iter<'a>(&'a self) -> Box<Iterator<Item=(usize, &'a T)> + 'a> { Box::new(self.0.iter().map(|(&i, c)| (i, c))) }
+    fn contains(&self, index: usize) -> bool { self.0.contains_key(&index) }
+}
+
+#[derive(Clone)]
+pub struct BTreeStorage<T>(BTreeMap<usize, T>);
+
+impl<T: 'static> Storage<T> for BTreeStorage<T>
+{
+    fn new() -> BTreeStorage<T> { BTreeStorage(BTreeMap::new()) }
+    fn len(&self) -> usize { self.0.len() }
+    fn get(&self, index: usize) -> Option<&T> { self.0.get(&index) }
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> { self.0.get_mut(&index) }
+    fn insert(&mut self, index: usize, component: T) -> Option<T> { self.0.insert(index, component) }
+    fn remove(&mut self, index: usize) -> Option<T> { self.0.remove(&index) }
+    fn iter<'a>(&'a self) -> Box<Iterator<Item=(usize, &'a T)> + 'a> { Box::new(self.0.iter().map(|(&i, c)| (i, c))) }
+    fn contains(&self, index: usize) -> bool { self.0.contains_key(&index) }
+}
+
+/// Keeps components packed in a `Vec`, with a sparse index from entities into it.
+///
+/// Removing a component moves the last one into its place.
+#[derive(Clone)]
+pub struct DenseStorage<T>
+{
+    sparse: VecMap<usize>,
+    indices: Vec<usize>,
+    components: Vec<T>,
+}
+
+impl<T> DenseStorage<T>
+{
+    /// Returns the packed components.
+    pub fn components(&self) -> &[T]
+    {
+        &self.components
+    }
+
+    /// Returns the packed components mutably.
+    pub fn components_mut(&mut self) -> &mut [T]
+    {
+        &mut self.components
+    }
+
+    /// Returns the entity index of each packed component.
+    pub fn indices(&self) -> &[usize]
+    {
+        &self.indices
+    }
+}
+
+impl<T: 'static> Storage<T> for DenseStorage<T>
+{
+    fn new() -> DenseStorage<T>
+    {
+        DenseStorage
+        {
+            sparse: VecMap::new(),
+            indices: Vec::new(),
+            components: Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize
+    {
+        self.components.len()
+    }
+
+    fn get(&self, index: usize) -> Option<&T>
+    {
+        self.sparse.get(&index).map(|&i| &self.components[i])
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T>
+    {
+        match self.sparse.get(&index)
+        {
+            Some(&i) => Some(&mut self.components[i]),
+            None => None,
+        }
+    }
+
+    fn insert(&mut self, index: usize, component: T) -> Option<T>
+    {
+        if let Some(&i) = self.sparse.get(&index)
+        {
+            return Some(mem::replace(&mut self.components[i], component));
+        }
+        self.sparse.insert(index, self.components.len());
+        self.indices.push(index);
+        self.components.push(component);
+        None
+    }
+
+    fn remove(&mut self, index: usize) -> Option<T>
+    {
+        let i = match self.sparse.remove(&index)
+        {
+            Some(i) => i,
+            None => return None,
+        };
+        self.indices.swap_remove(i);
+        if let Some(&moved) = self.indices.get(i)
+        {
+            self.sparse.insert(moved, i);
+        }
+        Some(self.components.swap_remove(i))
+    }
+
+    fn iter<'a>(&'a self) -> Box<Iterator<Item=(usize, &'a T)> + 'a>
+    {
+        Box::new(self.indices.iter().cloned().zip(self.components.iter()))
+    }
+
+    fn contains(&self, index: usize) -> bool
+    {
+        self.sparse.contains_key(&index)
+    }
+}
+
+/// Stores zero-sized marker components as a bit set.
+#[derive(Clone)]
+pub struct TagStorage<T>
+{
+    bits: Vec<u64>,
+    // Zero-sized, so this never allocates. Any element can stand in for any entity's tag.
+    tags: Vec<T>,
+}
+
+impl<T: 'static> Storage<T> for TagStorage<T>
+{
+    fn new() -> TagStorage<T>
+    {
+        assert!(mem::size_of::<T>() == 0, "Tag storage can only hold zero-sized components");
+        TagStorage
+        {
+            bits: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize
+    {
+        self.tags.len()
+    }
+
+    fn get(&self, index: usize) -> Option<&T>
+    {
+        if self.contains(index) { self.tags.first() } else { None }
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T>
+    {
+        if self.contains(index) { self.tags.first_mut() } else { None }
+    }
+
+    fn insert(&mut self, index: usize, component: T) -> Option<T>
+    {
+        if self.contains(index)
+        {
+            return Some(component);
+        }
+        let (word, bit) = (index / 64, index % 64);
+        if word >= self.bits.len()
+        {
+            self.bits.resize(word + 1, 0);
+        }
+        self.bits[word] |= 1 << bit;
+        self.tags.push(component);
+        None
+    }
+
+    fn remove(&mut self, index: usize) -> Option<T>
+    {
+        if !self.contains(index)
+        {
+            return None;
+        }
+        self.bits[index / 64] &= !(1 << (index % 64));
+        self.tags.pop()
+    }
+
+    fn iter<'a>(&'a self) -> Box<Iterator<Item=(usize, &'a T)> + 'a>
+    {
+        let tag = self.tags.first();
+        Box::new((0..self.bits.len() * 64).filter(move |&i| self.contains(i)).map(move |i| (i, tag.unwrap())))
+    }
+
+    fn contains(&self, index: usize) -> bool
+    {
+        self.bits.get(index / 64).map_or(false, |word| word & (1 << (index % 64)) != 0)
+    }
+}
@@ -1,12 +1,12 @@
 
 //! Systems to specifically deal with entities.
 
-use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
+use vec_map::VecMap;
 
 use Aspect;
 use DataHelper;
-use {Entity, IndexedEntity};
+use IndexedEntity;
 use EntityData;
 use EntityIter;
 use {System, Process};
@@ -19,7 +19,7 @@ pub trait EntityProcess: System
 pub struct EntitySystem<T: EntityProcess>
 {
     pub inner: T,
-    interested: HashMap<Entity, IndexedEntity<T::Components>>,
+    interested: VecMap<IndexedEntity<T::Components>>,
     aspect: Aspect<T::Components>,
 }
 
@@ -29,7 +29,7 @@ impl<T: EntityProcess> EntitySystem<T>
     {
         EntitySystem
         {
-            interested: HashMap::new(),
+            interested: VecMap::new(),
             aspect: aspect,
             inner: inner,
         }
@@ -61,14 +61,14 @@ impl<T: EntityProcess> System for EntitySystem<T>
     {
         if self.aspect.check(entity, components)
         {
-            self.interested.insert(***entity, (**entity).__clone());
+            self.interested.insert(entity.index(), (**entity).__clone());
             self.inner.activated(entity, components, services);
         }
     }
 
     fn reactivated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
     {
-        if self.interested.contains_key(entity)
+        if self.interested.contains_key(&entity.index())
         {
             if self.aspect.check(entity, components)
             {
@@ -76,20 +76,20 @@ impl<T: EntityProcess> System for EntitySystem<T>
             }
             else
             {
-                self.interested.remove(entity);
+                self.interested.remove(&entity.index());
                 self.inner.deactivated(entity, components, services);
             }
         }
         else if self.aspect.check(entity, components)
         {
-            self.interested.insert(***entity, (**entity).__clone());
+            self.interested.insert(entity.index(), (**entity).__clone());
             self.inner.activated(entity, components, services);
         }
     }
 
     fn deactivated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
     {
-        if self.interested.remove(entity).is_some()
+        if self.interested.remove(&entity.index()).is_some()
         {
             self.inner.deactivated(entity, components, services);
         }
@@ -100,6 +100,6 @@ impl<T: EntityProcess> Process for EntitySystem<T>
 {
     fn process(&mut self, c: &mut DataHelper<T::Components, T::Services>)
     {
-        self.inner.process(EntityIter::Map(self.interested.values()), c);
+        self.inner.process(EntityIter::Indexed(self.interested.values()), c);
     }
 }
@@ -1,12 +1,12 @@
 
 //! System to specifically deal with interactions between two types of entity.
 
-use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
+use vec_map::VecMap;
 
 use Aspect;
 use DataHelper;
-use {Entity, IndexedEntity};
+use IndexedEntity;
 use EntityData;
 use EntityIter;
 use {Process, System};
@@ -19,8 +19,8 @@ pub trait InteractProcess: System
 pub struct InteractSystem<T: InteractProcess>
 {
     pub inner: T,
-    interested_a: HashMap<Entity, IndexedEntity<T::Components>>,
-    interested_b: HashMap<Entity, IndexedEntity<T::Components>>,
+    interested_a: VecMap<IndexedEntity<T::Components>>,
+    interested_b: VecMap<IndexedEntity<T::Components>>,
     aspect_a: Aspect<T::Components>,
     aspect_b: Aspect<T::Components>,
 }
@@ -48,8 +48,8 @@ impl<T: InteractProcess> InteractSystem<T>
     {
         InteractSystem
         {
-            interested_a: HashMap::new(),
-            interested_b: HashMap::new(),
+            interested_a: VecMap::new(),
+            interested_b: VecMap::new(),
             aspect_a: aspect_a,
             aspect_b: aspect_b,
             inner: inner,
@@ -65,19 +65,19 @@ impl<T: InteractProcess> System for InteractSystem<T>
     {
         if self.aspect_a.check(entity, components)
         {
-            self.interested_a.insert(***entity, (**entity).__clone());
+            self.interested_a.insert(entity.index(), (**entity).__clone());
             self.inner.activated(entity, components, services);
         }
         if self.aspect_b.check(entity, components)
         {
-            self.interested_b.insert(***entity, (**entity).__clone());
+            self.interested_b.insert(entity.index(), (**entity).__clone());
             self.inner.activated(entity, components, services);
         }
     }
 
     fn reactivated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
     {
-        if self.interested_a.contains_key(entity)
+        if self.interested_a.contains_key(&entity.index())
         {
             if self.aspect_a.check(entity, components)
             {
@@ -85,16 +85,16 @@ impl<T: InteractProcess> System for InteractSystem<T>
             }
             else
             {
-                self.interested_a.remove(entity);
+                self.interested_a.remove(&entity.index());
                 self.inner.deactivated(entity, components, services);
             }
         }
         else if self.aspect_a.check(entity, components)
         {
-            self.interested_a.insert(***entity, (**entity).__clone());
+            self.interested_a.insert(entity.index(), (**entity).__clone());
             self.inner.activated(entity, components, services);
         }
-        if self.interested_b.contains_key(entity)
+        if self.interested_b.contains_key(&entity.index())
         {
             if self.aspect_b.check(entity, components)
             {
@@ -102,24 +102,24 @@ impl<T: InteractProcess> System for InteractSystem<T>
             }
             else
             {
-                self.interested_b.remove(entity);
+                self.interested_b.remove(&entity.index());
                 self.inner.deactivated(entity, components, services);
             }
         }
         else if self.aspect_b.check(entity, components)
         {
-            self.interested_b.insert(***entity, (**entity).__clone());
+            self.interested_b.insert(entity.index(), (**entity).__clone());
             self.inner.activated(entity, components, services);
         }
     }
 
     fn deactivated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
     {
-        if self.interested_a.remove(entity).is_some()
+        if self.interested_a.remove(&entity.index()).is_some()
         {
             self.inner.deactivated(entity, components, services);
         }
-        if self.interested_b.remove(entity).is_some()
+        if self.interested_b.remove(&entity.index()).is_some()
         {
             self.inner.deactivated(entity, components, services);
         }
@@ -130,6 +130,6 @@ impl<T: InteractProcess> Process for InteractSystem<T>
 {
     fn process(&mut self, c: &mut DataHelper<T::Components, T::Services>)
     {
-        self.inner.process(EntityIter::Map(self.interested_a.values()), EntityIter::Map(self.interested_b.values()), c);
+        self.inner.process(EntityIter::Indexed(self.interested_a.values()), EntityIter::Indexed(self.interested_b.values()), c);
     }
 }
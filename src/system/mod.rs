@@ -5,16 +5,23 @@ pub use self::entity::{EntitySystem, EntityProcess};
 pub use self::interact::{InteractSystem, InteractProcess};
 pub use self::interval::{IntervalSystem};
 pub use self::lazy::{LazySystem};
+pub use self::parallel::{ParallelProcess, ComponentAccess};
+pub use self::reactive::{ReactiveSystem, ReactiveProcess, ReactiveBatch};
+pub use self::schedule::{Schedule, SystemDesc};
 
 use EntityData;
 use ComponentManager;
 use ServiceManager;
 use DataHelper;
+use self::parallel::Split;
 
 pub mod entity;
 pub mod interact;
 pub mod interval;
 pub mod lazy;
+pub mod parallel;
+pub mod reactive;
+pub mod schedule;
 
 /// Generic base system type.
 pub trait System
@@ -47,4 +54,27 @@ pub trait Process: System
 {
     /// Process the world.
     fn process(&mut self, &mut DataHelper<Self::Components, Self::Services>);
+
+    #[doc(hidden)]
+    fn __parallel(&mut self) -> Option<&mut ParallelProcess<Components=Self::Components, Services=Self::Services>>
+    {
+        None
+    }
+}
+
+impl<T: ParallelProcess> Process for T
+{
+    fn process(&mut self, data: &mut DataHelper<T::Components, T::Services>)
+    {
+        let mut split = Split::exclusive();
+        let (components, services, entities) = data.__split();
+        components.__split(&mut split);
+        let access = split.into_access(entities).pop().unwrap();
+        ParallelProcess::process(self, access, services);
+    }
+
+    fn __parallel(&mut self) -> Option<&mut ParallelProcess<Components=T::Components, Services=T::Services>>
+    {
+        Some(self)
+    }
 }
@@ -0,0 +1,157 @@
+//! Systems that can be processed concurrently.
+//!
+//! A `ParallelProcess` only sees the component lists it declared in the `systems!` macro:
+//!
+//! ```ignore
+//! active: {
+//!     movement: Movement = Movement => [reads(velocity), writes(position)],
+//!     animation: Animation = Animation => [writes(sprite)],
+//! }
+//! ```
+//!
+//! `World::update_parallel` runs neighbouring systems in the schedule at the same time when
+//! their declared lists don't conflict and no ordering constraint separates them. Without the
+//! `parallel` feature the same groups are processed one after another.
+
+use std::any::Any;
+
+use ComponentManager;
+use EntityIter;
+use entity::EntityManager;
+use System;
+
+/// A system that processes the world through a restricted view of the components.
+pub trait ParallelProcess: System + Send
+{
+    /// Process the world.
+    fn process<'a>(&mut self, ComponentAccess<'a, Self::Components>, &Self::Services);
+}
+
+/// Borrows of the component lists a parallel system declared.
+pub struct ComponentAccess<'a, C: ComponentManager>
+{
+    lists: Vec<(&'static str, Option<ListBorrow<'a>>)>,
+    entities: &'a EntityManager<C>,
+}
+
+// The erased lists are fields of `C`, so they are thread safe whenever `C` is.
+unsafe impl<'a, C: ComponentManager + Send + Sync> Send for ComponentAccess<'a, C> {}
+
+enum ListBorrow<'a>
+{
+    Read(&'a Any),
+    Write(&'a mut Any),
+}
+
+impl<'a, C: ComponentManager> ComponentAccess<'a, C>
+{
+    /// Returns a component list declared with `reads` (or `writes`, which is then given up).
+    ///
+    /// The list type is usually inferred: `let vel: &ComponentList<_, Velocity> = access.read("velocity");`
+    pub fn read<L: Any>(&mut self, name: &str) -> &'a L
+    {
+        let slot = self.slot(name);
+        let list: &'a Any = match slot.take()
+        {
+            Some(ListBorrow::Read(list)) => list,
+            Some(ListBorrow::Write(list)) => list,
+            None => panic!("Component list `{}` was already borrowed mutably", name),
+        };
+        *slot = Some(ListBorrow::Read(list));
+        list.downcast_ref().expect("Component list has a different type")
+    }
+
+    /// Returns a component list declared with `writes`. Each list can only be taken once.
+    pub fn write<L: Any>(&mut self, name: &str) -> &'a mut L
+    {
+        match self.slot(name).take()
+        {
+            Some(ListBorrow::Write(list)) => list.downcast_mut().expect("Component list has a different type"),
+            Some(ListBorrow::Read(_)) => panic!("Component list `{}` was not declared as written", name),
+            None => panic!("Component list `{}` was already borrowed mutably", name),
+        }
+    }
+
+    /// Iterates over every entity in the world.
+    pub fn entities(&self) -> EntityIter<'a, C>
+    {
+        self.entities.iter()
+    }
+
+    fn slot(&mut self, name: &str) -> &mut Option<ListBorrow<'a>>
+    {
+        match self.lists.iter_mut().find(|&&mut (n, _)| n == name)
+        {
+            Some(&mut (_, ref mut slot)) => slot,
+            None => panic!("Component list `{}` was not declared by this system", name),
+        }
+    }
+}
+
+/// Hands out the fields of a `ComponentManager` to the systems of one parallel group.
+#[doc(hidden)]
+pub struct Split<'a>
+{
+    access: Vec<(Vec<&'static str>, Vec<&'static str>)>,
+    lists: Vec<Vec<(&'static str, Option<ListBorrow<'a>>)>>,
+    exclusive: bool,
+}
+
+impl<'a> Split<'a>
+{
+    /// Splits for systems with these `(reads, writes)`, which must not conflict.
+    pub fn new(access: Vec<(Vec<&'static str>, Vec<&'static str>)>) -> Split<'a>
+    {
+        Split
+        {
+            lists: access.iter().map(|_| Vec::new()).collect(),
+            access: access,
+            exclusive: false,
+        }
+    }
+
+    /// Gives a single system write access to everything.
+    pub fn exclusive() -> Split<'a>
+    {
+        Split
+        {
+            lists: vec![Vec::new()],
+            access: Vec::new(),
+            exclusive: true,
+        }
+    }
+
+    /// Called by `ComponentManager::__split` for each of its fields.
+    pub fn list<L: Any>(&mut self, name: &'static str, list: &'a mut L)
+    {
+        if self.exclusive
+        {
+            self.lists[0].push((name, Some(ListBorrow::Write(list))));
+            return;
+        }
+        match self.access.iter().position(|&(_, ref writes)| writes.contains(&name))
+        {
+            Some(writer) => self.lists[writer].push((name, Some(ListBorrow::Write(list)))),
+            None => {
+                let list: &'a L = list;
+                for (i, &(ref reads, _)) in self.access.iter().enumerate()
+                {
+                    if reads.contains(&name)
+                    {
+                        self.lists[i].push((name, Some(ListBorrow::Read(list))));
+                    }
+                }
+            },
+        }
+    }
+
+    /// Returns one access per system, in the order they were given.
+    pub fn into_access<C: ComponentManager>(self, entities: &'a EntityManager<C>) -> Vec<ComponentAccess<'a, C>>
+    {
+        self.lists.into_iter().map(|lists| ComponentAccess
+        {
+            lists: lists,
+            entities: entities,
+        }).collect()
+    }
+}
@@ -0,0 +1,199 @@
+//! Systems that only process entities when something happened to them.
+
+use std::ops::{Deref, DerefMut};
+use vec_map::VecMap;
+
+use Aspect;
+use DataHelper;
+use {Entity, IndexedEntity};
+use EntityData;
+use EntityIter;
+use {Changes, Tick};
+use {System, Process};
+
+pub trait ReactiveProcess: System
+{
+    fn process<'a>(&mut self, ReactiveBatch<'a, Self::Components>, &mut DataHelper<Self::Components, Self::Services>);
+}
+
+/// Everything that happened to a `ReactiveSystem`'s entities since it last ran.
+pub struct ReactiveBatch<'a, C: 'a + ::ComponentManager>
+{
+    entered: &'a [IndexedEntity<C>],
+    modified: &'a [IndexedEntity<C>],
+    left: &'a [Entity],
+}
+
+impl<'a, C: ::ComponentManager> ReactiveBatch<'a, C>
+{
+    /// Entities that started matching the aspect.
+    pub fn entered(&self) -> EntityIter<'a, C>
+    {
+        EntityIter::Slice(self.entered.iter())
+    }
+
+    /// Entities that kept matching the aspect, but had a watched component added or changed.
+    pub fn modified(&self) -> EntityIter<'a, C>
+    {
+        EntityIter::Slice(self.modified.iter())
+    }
+
+    /// Entities that stopped matching the aspect, or were removed from the world.
+    pub fn left(&self) -> &'a [Entity]
+    {
+        self.left
+    }
+}
+
+/// Like an `EntitySystem`, but its `process` only sees entities that entered the aspect,
+/// left it, or had a watched component modified since the last time it ran.
+///
+/// Processing is skipped when nothing happened. Changes the system makes to watched
+/// components itself are not reported back to it.
+pub struct ReactiveSystem<T: ReactiveProcess>
+{
+    pub inner: T,
+    interested: VecMap<IndexedEntity<T::Components>>,
+    aspect: Aspect<T::Components>,
+    watches: Vec<(Box<Fn(&T::Components) -> &Changes<T::Components>>, Tick)>,
+    entered: Vec<IndexedEntity<T::Components>>,
+    left: Vec<Entity>,
+}
+
+impl<T: ReactiveProcess> ReactiveSystem<T>
+{
+    pub fn new(inner: T, aspect: Aspect<T::Components>) -> ReactiveSystem<T>
+    {
+        ReactiveSystem
+        {
+            interested: VecMap::new(),
+            aspect: aspect,
+            inner: inner,
+            watches: Vec::new(),
+            entered: Vec::new(),
+            left: Vec::new(),
+        }
+    }
+
+    /// Reports entities as modified when the given component list changes.
+    ///
+    /// eg: `ReactiveSystem::new(inner, aspect).watch(|c| c.position.changes())`
+    pub fn watch<F>(mut self, changes: F) -> ReactiveSystem<T>
+        where F: Fn(&T::Components) -> &Changes<T::Components> + 'static
+    {
+        self.watches.push((Box::new(changes), 0));
+        self
+    }
+
+    fn enter(&mut self, entity: &EntityData<T::Components>)
+    {
+        self.interested.insert(entity.index(), (**entity).__clone());
+        self.left.retain(|e| *e != ***entity);
+        self.entered.push((**entity).__clone());
+    }
+
+    fn leave(&mut self, entity: Entity)
+    {
+        let before = self.entered.len();
+        self.entered.retain(|e| **e != entity);
+        if self.entered.len() == before
+        {
+            self.left.push(entity);
+        }
+    }
+}
+
+impl<T: ReactiveProcess> Deref for ReactiveSystem<T>
+{
+    type Target = T;
+    fn deref(&self) -> &T
+    {
+        &self.inner
+    }
+}
+
+impl<T: ReactiveProcess> DerefMut for ReactiveSystem<T>
+{
+    fn deref_mut(&mut self) -> &mut T
+    {
+        &mut self.inner
+    }
+}
+
+impl<T: ReactiveProcess> System for ReactiveSystem<T>
+{
+    type Components = T::Components;
+    type Services = T::Services;
+    fn activated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, _: &mut T::Services)
+    {
+        if self.aspect.check(entity, components)
+        {
+            self.enter(entity);
+        }
+    }
+
+    fn reactivated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, _: &mut T::Services)
+    {
+        if self.interested.contains_key(&entity.index())
+        {
+            if !self.aspect.check(entity, components)
+            {
+                self.interested.remove(&entity.index());
+                self.leave(***entity);
+            }
+        }
+        else if self.aspect.check(entity, components)
+        {
+            self.enter(entity);
+        }
+    }
+
+    fn deactivated(&mut self, entity: &EntityData<T::Components>, _: &T::Components, _: &mut T::Services)
+    {
+        if self.interested.remove(&entity.index()).is_some()
+        {
+            self.leave(***entity);
+        }
+    }
+}
+
+impl<T: ReactiveProcess> Process for ReactiveSystem<T>
+{
+    fn process(&mut self, c: &mut DataHelper<T::Components, T::Services>)
+    {
+        let mut seen = VecMap::new();
+        for entity in &self.entered
+        {
+            seen.insert(entity.index(), ());
+        }
+        let mut modified = Vec::new();
+        for &(ref changes, last) in &self.watches
+        {
+            for entity in changes(&c.components).changed_since(last)
+            {
+                let interested = self.interested.get(&entity.index()).map_or(false, |e| **e == **entity);
+                if interested && seen.insert(entity.index(), ()).is_none()
+                {
+                    modified.push((*entity).__clone());
+                }
+            }
+        }
+
+        if !self.entered.is_empty() || !modified.is_empty() || !self.left.is_empty()
+        {
+            self.inner.process(ReactiveBatch
+            {
+                entered: &self.entered,
+                modified: &modified,
+                left: &self.left,
+            }, c);
+        }
+
+        self.entered.clear();
+        self.left.clear();
+        for &mut (ref changes, ref mut last) in &mut self.watches
+        {
+            *last = changes(&c.components).tick();
+        }
+    }
+}
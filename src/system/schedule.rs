@@ -0,0 +1,336 @@
+//! Ordering of active systems.
+//!
+//! Every active system is labelled with its field name in the `systems!` macro, and may add
+//! extra labels along with `before`/`after` constraints:
+//!
+//! ```ignore
+//! active: {
+//!     input: Input = Input,
+//!     physics: Physics = Physics => [label(simulation), after(input)],
+//!     render: Render = Render => [after(simulation)],
+//! }
+//! ```
+//!
+//! The constraints are sorted when the world is created. Systems that aren't constrained
+//! relative to each other keep their declaration order.
+//!
+//! Systems implementing `ParallelProcess` also declare the component lists they `reads` and
+//! `writes`, which lets `run_parallel` process them concurrently (see `system::parallel`).
+
+use DataHelper;
+use {EcsError, EcsResult};
+use {ComponentManager, ServiceManager};
+use Process;
+use super::parallel::{ComponentAccess, ParallelProcess, Split};
+
+/// Labels and ordering constraints of a single active system.
+pub struct SystemDesc
+{
+    name: &'static str,
+    labels: Vec<&'static str>,
+    before: Vec<&'static str>,
+    after: Vec<&'static str>,
+    reads: Vec<&'static str>,
+    writes: Vec<&'static str>,
+}
+
+impl SystemDesc
+{
+    /// Describes a system, labelling it with its name.
+    pub fn new(name: &'static str) -> SystemDesc
+    {
+        SystemDesc
+        {
+            name: name,
+            labels: vec![name],
+            before: Vec::new(),
+            after: Vec::new(),
+            reads: Vec::new(),
+            writes: Vec::new(),
+        }
+    }
+
+    /// Adds extra labels that other systems can be ordered against.
+    pub fn label(mut self, labels: &[&'static str]) -> SystemDesc
+    {
+        self.labels.extend(labels);
+        self
+    }
+
+    /// Runs this system before every system with one of these labels.
+    pub fn before(mut self, labels: &[&'static str]) -> SystemDesc
+    {
+        self.before.extend(labels);
+        self
+    }
+
+    /// Runs this system after every system with one of these labels.
+    pub fn after(mut self, labels: &[&'static str]) -> SystemDesc
+    {
+        self.after.extend(labels);
+        self
+    }
+
+    /// Declares component lists that the system only reads.
+    pub fn reads(mut self, lists: &[&'static str]) -> SystemDesc
+    {
+        self.reads.extend(lists);
+        self
+    }
+
+    /// Declares component lists that the system modifies.
+    pub fn writes(mut self, lists: &[&'static str]) -> SystemDesc
+    {
+        self.writes.extend(lists);
+        self
+    }
+
+    /// Returns the system's name.
+    pub fn name(&self) -> &'static str
+    {
+        self.name
+    }
+
+    fn has_label(&self, label: &str) -> bool
+    {
+        self.labels.iter().any(|&l| l == label)
+    }
+}
+
+/// The order in which active systems are processed.
+#[derive(Clone, Debug)]
+pub struct Schedule
+{
+    names: Vec<&'static str>,
+    order: Vec<usize>,
+    edges: Vec<Vec<usize>>,
+    access: Vec<(Vec<&'static str>, Vec<&'static str>)>,
+    // Whether each system implements `ParallelProcess`.
+    parallel: Vec<bool>,
+}
+
+impl Schedule
+{
+    /// Sorts systems by their constraints.
+    ///
+    /// Fails with `EcsError::UnknownLabel` if a constraint refers to a label no system has,
+    /// or with `EcsError::ScheduleCycle` if the constraints contradict each other. The lists
+    /// given to `reads` and `writes` are checked against the components when the world is
+    /// created.
+    pub fn new(systems: Vec<SystemDesc>) -> EcsResult<Schedule>
+    {
+        let count = systems.len();
+        let mut edges = vec![Vec::new(); count];
+        let mut incoming = vec![0; count];
+
+        for (i, system) in systems.iter().enumerate()
+        {
+            for &(labels, forward) in &[(&system.before, true), (&system.after, false)]
+            {
+                for &label in labels
+                {
+                    let mut found = false;
+                    for (j, other) in systems.iter().enumerate()
+                    {
+                        if other.has_label(label)
+                        {
+                            found = true;
+                            if i == j
+                            {
+                                continue;
+                            }
+                            let (from, to) = if forward { (i, j) } else { (j, i) };
+                            if !edges[from].contains(&to)
+                            {
+                                edges[from].push(to);
+                                incoming[to] += 1;
+                            }
+                        }
+                    }
+                    if !found
+                    {
+                        return Err(EcsError::UnknownLabel(label));
+                    }
+                }
+            }
+        }
+
+        // Kahn's algorithm, always picking the earliest declared system that is ready.
+        let mut order = Vec::with_capacity(count);
+        let mut done = vec![false; count];
+        while order.len() < count
+        {
+            let next = (0..count).find(|&i| !done[i] && incoming[i] == 0);
+            match next
+            {
+                Some(i) => {
+                    done[i] = true;
+                    order.push(i);
+                    for &to in &edges[i]
+                    {
+                        incoming[to] -= 1;
+                    }
+                },
+                None => {
+                    // Every remaining system still waits on another remaining system, so
+                    // walking backwards from any of them must eventually loop.
+                    let mut visited = vec![false; count];
+                    let mut current = (0..count).find(|&i| !done[i]).unwrap();
+                    while !visited[current]
+                    {
+                        visited[current] = true;
+                        current = (0..count).find(|&i| !done[i] && edges[i].contains(&current)).unwrap();
+                    }
+                    return Err(EcsError::ScheduleCycle(systems[current].name));
+                },
+            }
+        }
+
+        Ok(Schedule
+        {
+            names: systems.iter().map(|s| s.name).collect(),
+            order: order,
+            edges: edges,
+            access: systems.into_iter().map(|s| (s.reads, s.writes)).collect(),
+            parallel: vec![true; count],
+        })
+    }
+
+    /// Returns the names of the systems in the order they are processed.
+    pub fn order(&self) -> Vec<&'static str>
+    {
+        self.order.iter().map(|&i| self.names[i]).collect()
+    }
+
+    /// Processes the systems, which must be given in declaration order.
+    pub fn run<C, M>(&self, systems: &mut [&mut Process<Components=C, Services=M>], data: &mut DataHelper<C, M>)
+        where C: ComponentManager, M: ServiceManager
+    {
+        for &i in &self.order
+        {
+            systems[i].process(data);
+        }
+    }
+
+    /// Returns the groups of systems that `run_parallel` processes together.
+    ///
+    /// Only neighbouring parallel systems are grouped, and only if none of them writes a list
+    /// another one reads or writes, and no constraint orders them relative to each other.
+    /// Systems that declare `reads` or `writes` without implementing `ParallelProcess` run on
+    /// their own.
+    pub fn stages(&self) -> Vec<Vec<&'static str>>
+    {
+        self.groups(|i| self.is_parallel(i)).iter()
+            .map(|group| group.iter().map(|&i| self.names[i]).collect())
+            .collect()
+    }
+
+    /// Processes the systems like `run`, but processes each group of non-conflicting parallel
+    /// systems concurrently. Without the `parallel` feature the groups run one after another.
+    pub fn run_parallel<C, M>(&self, systems: &mut [&mut Process<Components=C, Services=M>], data: &mut DataHelper<C, M>)
+        where C: ComponentManager + Send + Sync, M: ServiceManager + Sync
+    {
+        let groups = self.groups(|i| self.is_parallel(i) && systems[i].__parallel().is_some());
+        for group in groups
+        {
+            if group.len() == 1
+            {
+                systems[group[0]].process(data);
+                continue;
+            }
+
+            let mut jobs: Vec<Option<&mut ParallelProcess<Components=C, Services=M>>> = group.iter().map(|_| None).collect();
+            for (i, system) in systems.iter_mut().enumerate()
+            {
+                if let Some(pos) = group.iter().position(|&g| g == i)
+                {
+                    jobs[pos] = system.__parallel();
+                }
+            }
+
+            let mut split = Split::new(group.iter().map(|&i| self.access[i].clone()).collect());
+            let (components, services, entities) = data.__split();
+            components.__split(&mut split);
+            let jobs = jobs.into_iter().map(|job| job.unwrap()).zip(split.into_access(entities)).collect();
+            run_jobs(jobs, services);
+        }
+    }
+
+    /// Checks that every list the systems read or write is one of `lists`.
+    #[doc(hidden)]
+    pub fn __check_lists(&self, lists: &[&'static str]) -> EcsResult<()>
+    {
+        for &(ref reads, ref writes) in &self.access
+        {
+            if let Some(&list) = reads.iter().chain(writes).find(|l| !lists.contains(l))
+            {
+                return Err(EcsError::UnknownComponent(list));
+            }
+        }
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    pub fn __set_parallel(&mut self, parallel: Vec<bool>)
+    {
+        self.parallel = parallel;
+    }
+
+    // Declares what it accesses, and can be given just that.
+    fn is_parallel(&self, i: usize) -> bool
+    {
+        let (ref reads, ref writes) = self.access[i];
+        self.parallel[i] && !(reads.is_empty() && writes.is_empty())
+    }
+
+    fn conflicts(&self, a: usize, b: usize) -> bool
+    {
+        let (ref a_reads, ref a_writes) = self.access[a];
+        let (ref b_reads, ref b_writes) = self.access[b];
+        self.edges[a].contains(&b) || self.edges[b].contains(&a) ||
+            a_writes.iter().any(|l| b_reads.contains(l) || b_writes.contains(l)) ||
+            b_writes.iter().any(|l| a_reads.contains(l))
+    }
+
+    fn groups<F>(&self, mut parallel: F) -> Vec<Vec<usize>> where F: FnMut(usize) -> bool
+    {
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut open = false;
+        for &i in &self.order
+        {
+            let is_parallel = parallel(i);
+            if open && is_parallel && groups.last().unwrap().iter().all(|&j| !self.conflicts(i, j))
+            {
+                groups.last_mut().unwrap().push(i);
+            }
+            else
+            {
+                groups.push(vec![i]);
+                open = is_parallel;
+            }
+        }
+        groups
+    }
+}
+
+#[cfg(feature="parallel")]
+fn run_jobs<'a, C, M>(jobs: Vec<(&'a mut ParallelProcess<Components=C, Services=M>, ComponentAccess<'a, C>)>, services: &M)
+    where C: ComponentManager + Send + Sync, M: ServiceManager + Sync
+{
+    ::rayon::scope(|scope| {
+        for (system, access) in jobs
+        {
+            scope.spawn(move |_| system.process(access, services));
+        }
+    });
+}
+
+#[cfg(not(feature="parallel"))]
+fn run_jobs<'a, C, M>(jobs: Vec<(&'a mut ParallelProcess<Components=C, Services=M>, ComponentAccess<'a, C>)>, services: &M)
+    where C: ComponentManager + Send + Sync, M: ServiceManager + Sync
+{
+    for (system, access) in jobs
+    {
+        system.process(access, services);
+    }
+}
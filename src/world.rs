@@ -1,13 +1,31 @@
 
 #[cfg(feature="serialisation")] use cereal::{CerealData, CerealResult};
 #[cfg(feature="serialisation")] use std::io::{Read, Write};
+#[cfg(feature="serde")] use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature="serde")] use serde::de::{DeserializeOwned, Error as DeError};
+#[cfg(feature="serde")] use serde::ser::Error as SerError;
+#[cfg(feature="serde")] use save::{self, Schema};
+#[cfg(feature="serde")] use delta::Delta;
+#[cfg(feature="serde")] use dump;
+#[cfg(feature="serde")] use std::collections::HashSet;
 
+use std::any::TypeId;
+use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
+use vec_map::VecMap;
 
-use {EntityData, ModifyData};
+use {BuildData, EntityData, ModifyData};
 use {Entity, IndexedEntity, EntityIter};
 use {EntityBuilder, EntityModifier};
-use entity::EntityManager;
+use EcsResult;
+use Process;
+use command::{Command, Commands};
+use event::{AnyChannel, EventChannel, EventIter, EventReader};
+use entity::{Descendants, EntityManager};
+use map::{EntityMap, __Merge};
+use prefab::Prefab;
+use system::Schedule;
+use system::parallel::Split;
 
 pub struct World<S> where S: SystemManager
 {
@@ -20,6 +38,29 @@ pub struct DataHelper<C, M> where C: ComponentManager, M: ServiceManager
     pub components: C,
     pub services: M,
     entities: EntityManager<C>,
+    commands: Commands<C>,
+    events: HashMap<TypeId, Box<AnyChannel>>,
+}
+
+/// A copy of a world's entities, components and services, taken by `World::snapshot`.
+///
+/// Commands and events are not part of a snapshot.
+#[derive(Clone)]
+pub struct Snapshot<C, M> where C: ComponentManager, M: ServiceManager
+{
+    components: C,
+    services: M,
+    entities: EntityManager<C>,
+}
+
+/// Saved like a `DataHelper`, so snapshots can be compared with worlds by `Delta`.
+#[cfg(feature="serde")]
+impl<C: ComponentManager, M: ServiceManager> Serialize for Snapshot<C, M> where C: Serialize, M: Serialize
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        (&self.services, &self.entities, &self.components).serialize(serializer)
+    }
 }
 
 pub trait ComponentManager: 'static+Sized
@@ -27,7 +68,25 @@ pub trait ComponentManager: 'static+Sized
     #[doc(hidden)]
     fn __new() -> Self;
     #[doc(hidden)]
+    fn __lists() -> &'static [&'static str] where Self: Sized;
+    #[doc(hidden)]
     fn __remove_all(&mut self, &IndexedEntity<Self>);
+    #[doc(hidden)]
+    fn __clone_entity(&mut self, from: &IndexedEntity<Self>, to: &IndexedEntity<Self>);
+    #[doc(hidden)]
+    fn __loaded(&mut self, &IndexedEntity<Self>);
+    #[doc(hidden)]
+    fn __rewind(&mut self, current: &Self);
+    #[doc(hidden)]
+    fn __merge(&mut self, other: Self, merge: &__Merge<Self>);
+    #[doc(hidden)]
+    fn __remove_from(&mut self, list: &str, &IndexedEntity<Self>);
+    #[doc(hidden)]
+    fn __patch(&mut self, list: &str, other: &mut Self, &__Merge<Self>);
+    #[doc(hidden)]
+    fn __split<'a>(&'a mut self, &mut Split<'a>);
+    #[doc(hidden)]
+    fn __maintain(&mut self);
 }
 
 pub trait ServiceManager: 'static {}
@@ -41,6 +100,11 @@ pub trait SystemManager
     #[doc(hidden)]
     fn __new() -> Self;
     #[doc(hidden)]
+    fn __try_new() -> EcsResult<Self> where Self: Sized
+    {
+        Ok(Self::__new())
+    }
+    #[doc(hidden)]
     fn __activated(&mut self, EntityData<Self::Components>, &Self::Components, &mut Self::Services);
     #[doc(hidden)]
     fn __reactivated(&mut self, EntityData<Self::Components>, &Self::Components, &mut Self::Services);
@@ -48,6 +112,11 @@ pub trait SystemManager
     fn __deactivated(&mut self, EntityData<Self::Components>, &Self::Components, &mut Self::Services);
     #[doc(hidden)]
     fn __update(&mut self, &mut DataHelper<Self::Components, Self::Services>);
+    #[doc(hidden)]
+    fn __scheduled(&mut self) -> Option<(&Schedule, Vec<&mut Process<Components=Self::Components, Services=Self::Services>>)>
+    {
+        None
+    }
 }
 
 impl<S: SystemManager> Deref for World<S>
@@ -89,22 +158,208 @@ impl<C: ComponentManager, M: ServiceManager> DataHelper<C, M>
     pub fn with_entity_data<F, R>(&mut self, entity: &Entity, call: F) -> Option<R>
         where F: FnOnce(EntityData<C>, &mut C) -> R
     {
-        if self.entities.is_valid(entity) {
-            Some(call(EntityData(&self.entities.indexed(&entity).__clone()), self))
-        } else {
-            None
+        match self.entities.get(entity).map(|e| e.__clone()) {
+            Some(indexed) => Some(call(EntityData(&indexed), self)),
+            None => None,
         }
     }
 
+    /// Like `with_entity_data`, but reports why the entity could not be accessed.
+    ///
+    /// Entities that are queued for removal are rejected with `EcsError::PendingRemoval`.
+    pub fn try_with_entity_data<F, R>(&mut self, entity: &Entity, call: F) -> EcsResult<R>
+        where F: FnOnce(EntityData<C>, &mut C) -> R
+    {
+        let indexed = try!(self.entities.try_indexed(entity)).__clone();
+        Ok(call(EntityData(&indexed), self))
+    }
+
     pub fn create_entity<B>(&mut self, builder: B) -> Entity where B: EntityBuilder<C>
     {
         self.entities.create_entity(builder, &mut self.components)
     }
 
+    /// Creates a copy of an entity, with a clone of each of its components.
+    ///
+    /// Components that don't implement `Clone` are left out, as are the entity's children and
+    /// relations. Panics if the entity is not valid; see `try_clone_entity`.
+    pub fn clone_entity(&mut self, entity: &Entity) -> Entity
+    {
+        match self.try_clone_entity(entity)
+        {
+            Ok(clone) => clone,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    pub fn try_clone_entity(&mut self, entity: &Entity) -> EcsResult<Entity>
+    {
+        self.entities.clone_entity(entity, &mut self.components)
+    }
+
+    /// Moves the entities of another world into this one, giving them new ids.
+    ///
+    /// Components implementing `MapEntities` have their entity ids rewritten, and parents
+    /// stay parents, as do relations. The other world's services are dropped. Returns the new id of each entity.
+    pub fn merge(&mut self, other: DataHelper<C, M>) -> EntityMap
+    {
+        self.entities.merge(other.entities, &mut self.components, other.components)
+    }
+
+    /// Creates an entity from a prefab, along with entities for its children.
+    ///
+    /// The overrides are applied to the new entity after the prefab's own components, so they
+    /// can replace any of them. They only apply to the root: the children are spawned exactly as
+    /// their prefabs describe, and can be changed afterwards through `children()`.
+    pub fn spawn_prefab<B>(&mut self, prefab: &Prefab<C>, overrides: B) -> Entity where B: EntityBuilder<C>
+    {
+        let entity = self.create_entity(|e: BuildData<C>, c: &mut C| {
+            prefab.__build(&e, c);
+            overrides.build(e, c);
+        });
+        for child in prefab.children()
+        {
+            let child = self.spawn_prefab(child, ());
+            self.entities.set_parent(child, Some(entity)).unwrap();
+        }
+        entity
+    }
+
+    /// Queues an entity for removal. Removing a dead or already queued entity does nothing.
     pub fn remove_entity(&mut self, entity: Entity)
     {
         self.entities.remove_entity(entity);
     }
+
+    pub fn try_remove_entity(&mut self, entity: Entity) -> EcsResult<()>
+    {
+        self.entities.try_remove_entity(entity)
+    }
+
+    /// Attaches an entity to a parent, or detaches it with `None`.
+    ///
+    /// Removing an entity also removes all of its descendants.
+    pub fn set_parent(&mut self, child: Entity, parent: Option<Entity>) -> EcsResult<()>
+    {
+        self.entities.set_parent(child, parent)
+    }
+
+    pub fn parent(&self, entity: &Entity) -> Option<Entity>
+    {
+        self.entities.parent(entity)
+    }
+
+    pub fn children(&self, entity: &Entity) -> &[Entity]
+    {
+        self.entities.children(entity)
+    }
+
+    pub fn descendants(&self, entity: &Entity) -> Descendants<C>
+    {
+        self.entities.descendants(entity)
+    }
+
+    /// Adds a relation of type `R` from the source to the target.
+    ///
+    /// Returns false if the relation already existed. See `relation` for how removed entities
+    /// are handled.
+    pub fn relate<R: 'static>(&mut self, source: Entity, target: Entity) -> EcsResult<bool>
+    {
+        self.entities.relate::<R>(source, target)
+    }
+
+    pub fn unrelate<R: 'static>(&mut self, source: &Entity, target: &Entity) -> bool
+    {
+        self.entities.unrelate::<R>(source, target)
+    }
+
+    /// Returns every entity the source has a relation of type `R` to.
+    pub fn targets<R: 'static>(&self, source: &Entity) -> &[Entity]
+    {
+        self.entities.targets::<R>(source)
+    }
+
+    /// Returns every entity with a relation of type `R` to the target.
+    pub fn sources<R: 'static>(&self, target: &Entity) -> &[Entity]
+    {
+        self.entities.sources::<R>(target)
+    }
+
+    /// Returns the queue of changes to apply when the world is next flushed.
+    pub fn commands(&mut self) -> &mut Commands<C>
+    {
+        &mut self.commands
+    }
+
+    /// Returns the channel for events of type `E`, creating it if needed.
+    pub fn events<E: Send + 'static>(&mut self) -> &mut EventChannel<E>
+    {
+        self.events.entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(EventChannel::<E>::new()))
+            .as_any_mut().downcast_mut().unwrap()
+    }
+
+    /// Returns the events of type `E` the reader hasn't seen yet.
+    pub fn read_events<E: Send + 'static>(&self, reader: &mut EventReader<E>) -> EventIter<E>
+    {
+        match self.events.get(&TypeId::of::<E>())
+        {
+            Some(channel) => channel.as_any().downcast_ref::<EventChannel<E>>().unwrap().read(reader),
+            None => EventIter::empty(),
+        }
+    }
+
+    /// Returns true if the entity exists.
+    ///
+    /// An entity queued for removal stays valid until the next flush; the `try_*` methods
+    /// report it as `EcsError::PendingRemoval`.
+    pub fn is_valid(&self, entity: &Entity) -> bool
+    {
+        self.entities.is_valid(entity)
+    }
+
+    #[doc(hidden)]
+    pub fn __split(&mut self) -> (&mut C, &M, &EntityManager<C>)
+    {
+        (&mut self.components, &self.services, &self.entities)
+    }
+
+    /// Moves the components of `other`, stored at the given indices, onto the matching entities.
+    #[doc(hidden)]
+    pub fn __merge_components(&mut self, other: C, targets: &[(usize, Entity)], map: EntityMap) -> EntityMap
+    {
+        let mut indices = VecMap::new();
+        for &(index, entity) in targets
+        {
+            // Components for entities that are gone or going are dropped.
+            if let Ok(indexed) = self.entities.try_indexed(&entity)
+            {
+                indices.insert(index, indexed.__clone());
+            }
+        }
+        let merge = __Merge { indices: indices, map: map };
+        self.components.__merge(other, &merge);
+        merge.map
+    }
+
+    // Stamps loaded components, so changes to them are tracked.
+    fn loaded(mut self) -> DataHelper<C, M>
+    {
+        for entity in self.entities.iter()
+        {
+            self.components.__loaded(&entity);
+        }
+        self
+    }
+
+    #[doc(hidden)]
+    pub fn __remove_from(&mut self, list: &str, entity: &Entity)
+    {
+        if let Ok(indexed) = self.entities.try_indexed(entity)
+        {
+            self.components.__remove_from(list, indexed);
+        }
+    }
 }
 
 #[cfg(feature="serialisation")]
@@ -119,31 +374,245 @@ unsafe impl<C: ComponentManager, M: ServiceManager> CerealData for DataHelper<C,
         let services = try!(CerealData::read(r));
         let entities = try!(CerealData::read(r));
         let components = try!(CerealData::read(r));
-        Ok(DataHelper {
+        let data: DataHelper<C, M> = DataHelper {
             components: components,
             services: services,
             entities: entities,
-        })
+            commands: Commands::new(),
+            events: HashMap::new(),
+        };
+        Ok(data.loaded())
     }
 }
 
 #[cfg(feature="serialisation")]
 impl<S: SystemManager> World<S> where DataHelper<S::Components, S::Services>: CerealData {
+    #[deprecated(note="use the `serde` feature instead")]
     pub fn load(reader: &mut Read) -> CerealResult<World<S>> {
         let mut world = World {
             systems: S::__new(),
             data: try!(CerealData::read(reader)),
         };
-        world.refresh();
+        world.restore();
         Ok(world)
     }
 
-    pub fn save(&mut self, writer: &mut Write) -> CerealResult<()> {
-        self.flush_queue();
+    #[deprecated(note="use the `serde` feature instead")]
+    pub fn save(&self, writer: &mut Write) -> CerealResult<()> {
         self.data.write(writer)
     }
 }
 
+// Saved as the services, the entities, then the components.
+#[cfg(feature="serde")]
+impl<C: ComponentManager, M: ServiceManager> Serialize for DataHelper<C, M> where C: Serialize, M: Serialize
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        (&self.services, &self.entities, &self.components).serialize(serializer)
+    }
+}
+
+#[cfg(feature="serde")]
+impl<'de, C: ComponentManager, M: ServiceManager> Deserialize<'de> for DataHelper<C, M>
+    where C: Deserialize<'de>, M: Deserialize<'de>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        let (services, entities, components) = try!(Deserialize::deserialize(deserializer));
+        let data: DataHelper<C, M> = DataHelper {
+            components: components,
+            services: services,
+            entities: entities,
+            commands: Commands::new(),
+            events: HashMap::new(),
+        };
+        Ok(data.loaded())
+    }
+}
+
+/// Saves the world's data.
+///
+/// Entities waiting to be created or removed are saved as such, and handled by the first flush
+/// after loading. Queued `Commands`, events and relations are not saved.
+#[cfg(feature="serde")]
+impl<S: SystemManager> Serialize for World<S> where DataHelper<S::Components, S::Services>: Serialize
+{
+    fn serialize<R: Serializer>(&self, serializer: R) -> Result<R::Ok, R::Error>
+    {
+        self.data.serialize(serializer)
+    }
+}
+
+/// Loads the world's data, then creates the systems and tells them about every entity.
+///
+/// The loaded world has no relations, since they are not saved.
+#[cfg(feature="serde")]
+impl<'de, S: SystemManager> Deserialize<'de> for World<S> where DataHelper<S::Components, S::Services>: Deserialize<'de>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<World<S>, D::Error>
+    {
+        let mut world = World {
+            systems: S::__new(),
+            data: try!(Deserialize::deserialize(deserializer)),
+        };
+        world.restore();
+        Ok(world)
+    }
+}
+
+#[cfg(feature="serde")]
+impl<S: SystemManager> World<S>
+{
+    /// Saves the world with a header describing the version of each component list.
+    ///
+    /// See the `save` module.
+    pub fn save_versioned<R: Serializer>(&self, schema: &Schema, serializer: R) -> Result<R::Ok, R::Error>
+        where DataHelper<S::Components, S::Services>: Serialize
+    {
+        save::__save(&self.data, schema, serializer)
+    }
+
+    /// Saves some of the world's entities, along with their descendants and components.
+    ///
+    /// The save has the same layout as a whole world, so `merge_saved` adds the entities to
+    /// another world, and it can also be loaded as a world of its own. The services are saved
+    /// with their default values, since they belong to the whole world. eg, to export
+    /// everything in an inventory:
+    ///
+    /// ```ignore
+    /// let items = world.entities().filter(aspect!(<MyComponents> all: [item]), &world.data.components).map(|e| **e);
+    /// world.save_entities(items, &mut serde_json::Serializer::new(file))?;
+    /// ```
+    pub fn save_entities<I, R>(&self, entities: I, serializer: R) -> Result<R::Ok, R::Error>
+        where I: IntoIterator<Item=Entity>, R: Serializer, DataHelper<S::Components, S::Services>: Serialize,
+              S::Services: Default + Serialize
+    {
+        let mut keep = HashSet::new();
+        for entity in entities
+        {
+            if keep.insert(entity)
+            {
+                keep.extend(self.data.descendants(&entity));
+            }
+        }
+        save::__save_entities(&self.data, &S::Services::default(), &keep, serializer)
+    }
+
+    /// Loads saved data into this world, like `DataHelper::merge`.
+    pub fn merge_saved<'de, D: Deserializer<'de>>(&mut self, deserializer: D) -> Result<EntityMap, D::Error>
+        where DataHelper<S::Components, S::Services>: Deserialize<'de>
+    {
+        let other: DataHelper<S::Components, S::Services> = try!(Deserialize::deserialize(deserializer));
+        let map = self.data.merge(other);
+        Ok(map)
+    }
+
+    /// Loads a world saved by `save_versioned`, upgrading components saved at older versions.
+    pub fn load_versioned<'de, D: Deserializer<'de>>(schema: &Schema, deserializer: D) -> Result<World<S>, D::Error>
+        where DataHelper<S::Components, S::Services>: DeserializeOwned
+    {
+        let mut world = World {
+            systems: S::__new(),
+            data: try!(save::__load(schema, deserializer)),
+        };
+        world.restore();
+        Ok(world)
+    }
+
+    /// Saves the world entity by entity, with components named after their field.
+    ///
+    /// Meant for readable formats like JSON and RON; see the `dump` module.
+    pub fn dump<R: Serializer>(&self, serializer: R) -> Result<R::Ok, R::Error>
+        where DataHelper<S::Components, S::Services>: Serialize
+    {
+        try!(dump::__dump(&self.data).map_err(R::Error::custom)).serialize(serializer)
+    }
+
+    /// Loads a world saved by `dump`, or written by hand in the same layout.
+    pub fn load_dump<'de, D: Deserializer<'de>>(deserializer: D) -> Result<World<S>, D::Error>
+        where DataHelper<S::Components, S::Services>: DeserializeOwned, S::Components: Serialize
+    {
+        let value = try!(save::Value::deserialize(deserializer));
+        let mut world = World {
+            systems: S::__new(),
+            data: try!(dump::__undump(value, &S::Components::__new()).map_err(D::Error::custom)),
+        };
+        world.restore();
+        Ok(world)
+    }
+
+    /// Brings the world from the state a delta was taken from to the state it was taken to.
+    ///
+    /// Systems are told about the entities the delta created, removed or changed, and the
+    /// components it touches are recorded in their lists' changes. Relations between entities
+    /// that are still alive are kept. See the `delta` module.
+    pub fn apply_delta(&mut self, delta: &Delta) -> Result<(), String>
+        where DataHelper<S::Components, S::Services>: Serialize + DeserializeOwned
+    {
+        // Going through the serialised form checks the delta fits this world, and gives the
+        // new components their types.
+        let value = try!(::serde_value::to_value(&self.data).map_err(|e| e.to_string()));
+        let mut data: DataHelper<S::Components, S::Services> =
+            try!(try!(delta.__apply(value)).deserialize_into().map_err(|e| e.to_string()));
+        let touched = delta.__touched();
+        let old: HashSet<Entity> = self.data.entities.__activated().into_iter().collect();
+        let new = data.entities.__activated();
+        let kept: HashSet<Entity> = new.iter().cloned().collect();
+        for entity in &old
+        {
+            if !kept.contains(entity)
+            {
+                let indexed = self.data.entities.indexed(entity);
+                self.systems.__deactivated(EntityData(indexed), &self.data.components, &mut self.data.services);
+            }
+        }
+
+        // Only the components the delta mentions are touched, so their changes are tracked.
+        let before: HashMap<usize, Entity> = self.data.entities.iter().map(|e| (e.index(), **e)).collect();
+        for (name, list) in delta.__components()
+        {
+            for index in &list.removed
+            {
+                if let Some(entity) = before.get(index)
+                {
+                    self.data.__remove_from(name, entity);
+                }
+            }
+        }
+        self.data.entities.__patch(data.entities);
+        let mut after = VecMap::new();
+        for entity in self.data.entities.iter()
+        {
+            after.insert(entity.index(), entity.__clone());
+        }
+        for (name, list) in delta.__components()
+        {
+            let mut indices = VecMap::new();
+            for &(index, _) in list.added.iter().chain(&list.changed)
+            {
+                if let Some(entity) = after.get(&index)
+                {
+                    indices.insert(index, entity.__clone());
+                }
+            }
+            let merge = __Merge { indices: indices, map: EntityMap::new() };
+            self.data.components.__patch(name, &mut data.components, &merge);
+        }
+        self.data.services = data.services;
+
+        for entity in new
+        {
+            if !old.contains(&entity) || touched.contains(&entity.index())
+            {
+                let indexed = self.data.entities.indexed(&entity);
+                self.systems.__reactivated(EntityData(indexed), &self.data.components, &mut self.data.services);
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<S: SystemManager> World<S>
 {
     pub fn new() -> World<S> where S::Services: Default
@@ -154,6 +623,8 @@ impl<S: SystemManager> World<S>
                 components: S::Components::__new(),
                 services: S::Services::default(),
                 entities: EntityManager::new(),
+                commands: Commands::new(),
+                events: HashMap::new(),
             },
         }
     }
@@ -166,10 +637,33 @@ impl<S: SystemManager> World<S>
                 components: S::Components::__new(),
                 services: services,
                 entities: EntityManager::new(),
+                commands: Commands::new(),
+                events: HashMap::new(),
             },
         }
     }
 
+    /// Like `new`, but returns an error instead of panicking if the systems can't be ordered.
+    pub fn try_new() -> EcsResult<World<S>> where S::Services: Default
+    {
+        World::try_with_services(S::Services::default())
+    }
+
+    /// Like `with_services`, but returns an error instead of panicking if the systems can't be ordered.
+    pub fn try_with_services(services: S::Services) -> EcsResult<World<S>>
+    {
+        Ok(World {
+            systems: try!(S::__try_new()),
+            data: DataHelper {
+                components: S::Components::__new(),
+                services: services,
+                entities: EntityManager::new(),
+                commands: Commands::new(),
+                events: HashMap::new(),
+            },
+        })
+    }
+
     pub fn entities(&self) -> EntityIter<S::Components>
     {
         self.data.entities.iter()
@@ -184,6 +678,18 @@ impl<S: SystemManager> World<S>
         );
     }
 
+    /// Like `modify_entity`, but fails instead of panicking when the entity is gone
+    /// or queued for removal.
+    pub fn try_modify_entity<M>(&mut self, entity: Entity, modifier: M) -> EcsResult<()> where M: EntityModifier<S::Components>
+    {
+        let indexed = try!(self.data.entities.try_indexed(&entity));
+        modifier.modify(ModifyData(indexed), &mut self.data.components);
+        self.systems.__reactivated(
+            EntityData(indexed), &self.data.components, &mut self.data.services
+        );
+        Ok(())
+    }
+
     pub fn refresh(&mut self)
     {
         self.flush_queue();
@@ -192,19 +698,106 @@ impl<S: SystemManager> World<S>
         }
     }
 
+    /// Copies the world's entities, components and services, so it can be rolled back later.
+    ///
+    /// Needs the components struct to derive `Clone`, which in turn needs every component to.
+    pub fn snapshot(&self) -> Snapshot<S::Components, S::Services>
+        where S::Components: Clone, S::Services: Clone
+    {
+        Snapshot
+        {
+            components: self.data.components.clone(),
+            services: self.data.services.clone(),
+            entities: self.data.entities.clone(),
+        }
+    }
+
+    /// Puts the world back to how it was when the snapshot was taken.
+    ///
+    /// Systems are told that the current entities were deactivated, and that the snapshot's
+    /// were activated. Queued commands are dropped. Components that the rollback puts back,
+    /// reverts or takes away show up in their list's `changes()` as of a new tick.
+    pub fn rollback(&mut self, snapshot: &Snapshot<S::Components, S::Services>)
+        where S::Components: Clone, S::Services: Clone
+    {
+        for entity in self.data.entities.__activated()
+        {
+            let indexed = self.data.entities.indexed(&entity);
+            self.systems.__deactivated(EntityData(indexed), &self.data.components, &mut self.data.services);
+        }
+        let mut components = snapshot.components.clone();
+        components.__rewind(&self.data.components);
+        self.data.components = components;
+        self.data.services.clone_from(&snapshot.services);
+        self.data.entities = snapshot.entities.clone();
+        self.data.commands = Commands::new();
+        self.restore();
+    }
+
+    // Tells the systems about every entity in freshly replaced data, except those that are
+    // still waiting to be activated by the next flush.
+    fn restore(&mut self)
+    {
+        for entity in self.data.entities.__activated()
+        {
+            let indexed = self.data.entities.indexed(&entity);
+            self.systems.__reactivated(EntityData(indexed), &self.data.components, &mut self.data.services);
+        }
+    }
+
     pub fn flush_queue(&mut self)
+    {
+        self.flush_entities();
+        for command in self.data.commands.__take()
+        {
+            match command
+            {
+                Command::Create(builder) => { self.data.create_entity(builder); },
+                Command::Remove(entity) => self.data.remove_entity(entity),
+                Command::Modify(entity, modifier) => { let _ = self.try_modify_entity(entity, modifier); },
+            }
+            self.flush_entities();
+        }
+    }
+
+    fn maintain(&mut self)
+    {
+        self.data.components.__maintain();
+        for channel in self.data.events.values_mut()
+        {
+            channel.maintain();
+        }
+    }
+
+    fn flush_entities(&mut self)
     {
         self.data.entities.flush_queue(
             &mut self.data.components,
             &mut self.data.services,
             &mut self.systems
         );
+        self.data.entities.__notify_relations(&mut self.data.events);
     }
 
     pub fn update(&mut self)
     {
+        self.maintain();
         self.flush_queue();
         self.systems.__update(&mut self.data);
         self.flush_queue();
     }
+
+    /// Like `update`, but processes groups of parallel systems concurrently.
+    ///
+    /// See `system::parallel` for how systems declare the component lists they use.
+    pub fn update_parallel(&mut self) where S::Components: Send + Sync, S::Services: Sync
+    {
+        self.maintain();
+        self.flush_queue();
+        match self.systems.__scheduled() {
+            Some((schedule, mut systems)) => schedule.run_parallel(&mut systems, &mut self.data),
+            None => self.systems.__update(&mut self.data),
+        }
+        self.flush_queue();
+    }
 }
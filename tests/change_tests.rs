@@ -0,0 +1,69 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{Entity, World};
+
+components! {
+    #[builder(Parts)]
+    struct Body {
+        #[hot] position: i32,
+        #[cold] name: &'static str,
+    }
+}
+
+systems! {
+    struct NoSystems<Body, ()>;
+}
+
+fn changed(world: &World<NoSystems>, since: ecs::Tick) -> Vec<Entity>
+{
+    world.position.changes().changed_since(since).map(|e| **e).collect()
+}
+
+fn added(world: &World<NoSystems>, since: ecs::Tick) -> Vec<Entity>
+{
+    world.position.changes().added_since(since).map(|e| **e).collect()
+}
+
+#[test]
+fn test_change_tracking()
+{
+    let mut world = World::<NoSystems>::new();
+    let first = world.create_entity(Parts { position: Some(1), name: Some("first") });
+    let second = world.create_entity(Parts { position: Some(2), ..Default::default() });
+    assert_eq!(vec![first, second], added(&world, 0));
+    assert_eq!(vec![first], world.name.changes().added_since(0).map(|e| **e).collect::<Vec<_>>());
+
+    // Reading doesn't count as a change, mutable access does.
+    let start = world.position.changes().tick();
+    world.with_entity_data(&second, |e, c| c.position[e]);
+    assert!(changed(&world, start).is_empty());
+    world.with_entity_data(&second, |e, c| c.position[e] += 1);
+    assert_eq!(vec![second], changed(&world, start));
+    assert!(added(&world, start).is_empty());
+
+    // Replacing a component is a change, adding one after removal is an addition.
+    let start = world.position.changes().tick();
+    world.modify_entity(first, |e: ecs::ModifyData<Body>, c: &mut Body| { c.position.insert(&e, 5); });
+    assert_eq!(vec![first], changed(&world, start));
+    world.modify_entity(first, |e: ecs::ModifyData<Body>, c: &mut Body| { c.position.remove(&e); });
+    assert_eq!(vec![first], world.position.changes().removed_since(start).collect::<Vec<_>>());
+    assert!(changed(&world, start).is_empty());
+    world.modify_entity(first, |e: ecs::ModifyData<Body>, c: &mut Body| { c.position.insert(&e, 6); });
+    assert_eq!(vec![first], added(&world, start));
+}
+
+#[test]
+fn test_removals_expire()
+{
+    let mut world = World::<NoSystems>::new();
+    let entity = world.create_entity(Parts { position: Some(1), ..Default::default() });
+    world.remove_entity(entity);
+    world.flush_queue();
+    assert_eq!(vec![entity], world.position.changes().removed_since(0).collect::<Vec<_>>());
+
+    world.update();
+    assert_eq!(vec![entity], world.position.changes().removed_since(0).collect::<Vec<_>>());
+    world.update();
+    assert!(world.position.changes().removed_since(0).next().is_none());
+}
@@ -0,0 +1,54 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{BuildData, World};
+
+/// A component that can't be cloned.
+#[derive(Debug, PartialEq)]
+pub struct Handle(u32);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bullet;
+
+components! {
+    struct Body {
+        #[hot] position: (i32, i32),
+        #[cold] name: String,
+        #[tag] bullet: Bullet,
+        #[dense] handle: Handle,
+    }
+}
+
+systems! {
+    struct NoSystems<Body, ()>;
+}
+
+#[test]
+fn test_clone_entity()
+{
+    let mut world = World::<NoSystems>::new();
+    let original = world.create_entity(|e: BuildData<Body>, c: &mut Body| {
+        c.position.add(&e, (1, 2));
+        c.name.add(&e, "bullet".to_string());
+        c.bullet.add(&e, Bullet);
+        c.handle.add(&e, Handle(7));
+    });
+    let tick = world.position.changes().tick();
+    let copy = world.clone_entity(&original);
+    assert!(copy != original);
+    world.flush_queue();
+    assert_eq!(2, world.entities().count());
+
+    world.with_entity_data(&copy, |e, c| {
+        assert_eq!((1, 2), c.position[e]);
+        assert_eq!("bullet", c.name[e]);
+        assert!(c.bullet.has(&e));
+        assert!(!c.handle.has(&e));
+    });
+    world.with_entity_data(&original, |e, c| c.position[e].0 = 10);
+    assert_eq!(Some(1), world.with_entity_data(&copy, |e, c| c.position[e].0));
+    assert_eq!(1, world.position.changes().added_since(tick).count());
+
+    world.remove_entity(original);
+    assert!(world.try_clone_entity(&original).is_err());
+}
@@ -0,0 +1,90 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{Commands, DataHelper, Entity, EntityIter, System, World};
+use ecs::system::{EntityProcess, EntitySystem};
+
+components! {
+    #[builder(Parts)]
+    struct Body {
+        #[hot] health: i32,
+        #[cold] target: Entity,
+        #[hot] burning: (),
+    }
+}
+
+/// Sets whatever an entity targets on fire, and spawns a new entity each time.
+pub struct Ignite;
+impl System for Ignite { type Components = Body; type Services = (); }
+impl EntityProcess for Ignite
+{
+    fn process(&mut self, entities: EntityIter<Body>, data: &mut DataHelper<Body, ()>)
+    {
+        for e in entities
+        {
+            let target = data.target[e];
+            data.commands().insert(target, |c| &mut c.burning, ());
+            data.commands().create_entity(Parts { health: Some(1), ..Default::default() });
+        }
+    }
+}
+
+/// Burns entities once, then puts the fire out.
+pub struct Burn;
+impl System for Burn { type Components = Body; type Services = (); }
+impl EntityProcess for Burn
+{
+    fn process(&mut self, entities: EntityIter<Body>, data: &mut DataHelper<Body, ()>)
+    {
+        let burning: Vec<Entity> = entities.map(|e| **e).collect();
+        for entity in burning
+        {
+            data.commands().modify_entity(entity, |e, c: &mut Body| c.health[e] -= 5);
+            data.commands().remove(entity, |c| &mut c.burning);
+        }
+    }
+}
+
+systems! {
+    struct BodySystems<Body, ()> {
+        active: {
+            ignite: EntitySystem<Ignite> = EntitySystem::new(Ignite, aspect!(<Body> all: [target])),
+            burn: EntitySystem<Burn> = EntitySystem::new(Burn, aspect!(<Body> all: [health, burning])),
+        },
+        passive: {}
+    }
+}
+
+#[test]
+fn test_commands()
+{
+    let mut world = World::<BodySystems>::new();
+    let victim = world.create_entity(Parts { health: Some(10), ..Default::default() });
+    world.create_entity(Parts { target: Some(victim), ..Default::default() });
+
+    // Nothing happens until the commands are flushed at the end of the update.
+    world.update();
+    assert!(world.with_entity_data(&victim, |e, c| c.burning.has(&e)).unwrap());
+    assert_eq!(3, world.entities().count());
+
+    // The burn system picked the victim up through `reactivated`. Its commands run after
+    // those of the ignite system, so the fire is put out again.
+    world.update();
+    assert_eq!(Some(5), world.with_entity_data(&victim, |e, c| c.health[e]));
+    assert!(!world.with_entity_data(&victim, |e, c| c.burning.has(&e)).unwrap());
+    assert_eq!(4, world.entities().count());
+
+    // Commands for removed entities are skipped.
+    world.data.commands().insert(victim, |c| &mut c.health, 100);
+    world.data.commands().remove_entity(victim);
+    world.data.commands().insert(victim, |c| &mut c.health, 100);
+    world.flush_queue();
+    assert!(!world.is_valid(&victim));
+}
+
+#[test]
+fn test_commands_are_send()
+{
+    fn assert_send<T: Send>() {}
+    assert_send::<Commands<Body>>();
+}
@@ -0,0 +1,160 @@
+#![cfg(feature="serde")]
+
+#[macro_use]
+extern crate ecs;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use ecs::{DataHelper, EntityIter, ModifyData, System, World};
+use ecs::delta::Delta;
+use ecs::system::{EntityProcess, EntitySystem};
+
+components! {
+    #[builder(Parts)]
+    #[derive(Clone, Serialize, Deserialize)]
+    struct Body {
+        #[hot] position: i32,
+        #[cold] name: String,
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Seen
+{
+    count: usize,
+}
+impl ecs::ServiceManager for Seen {}
+
+/// Counts the entities with a position.
+pub struct Count;
+impl System for Count { type Components = Body; type Services = Seen; }
+impl EntityProcess for Count
+{
+    fn process(&mut self, entities: EntityIter<Body>, data: &mut DataHelper<Body, Seen>)
+    {
+        data.services.count = entities.count();
+    }
+}
+
+systems! {
+    struct Sync<Body, Seen> {
+        active: {
+            count: EntitySystem<Count> = EntitySystem::new(Count, aspect!(<Body> all: [position])),
+        },
+        passive: {}
+    }
+}
+
+#[test]
+fn test_sync()
+{
+    let mut server = World::<Sync>::new();
+    let ship = server.create_entity(Parts { name: Some("ship".to_string()), position: Some(0) });
+    let rock = server.create_entity(Parts { name: Some("rock".to_string()), position: Some(5) });
+    let moon = server.create_entity(Parts { name: Some("moon".to_string()), position: Some(9) });
+    server.flush_queue();
+    let base = server.snapshot();
+    let mut client: World<Sync> = serde_json::from_value(serde_json::to_value(&server).unwrap()).unwrap();
+
+    server.with_entity_data(&ship, |e, c| c.position[e] = 3);
+    server.modify_entity(moon, |e: ModifyData<Body>, c: &mut Body| { c.position.remove(&e); });
+    server.remove_entity(rock);
+    let probe = server.create_entity(Parts { name: Some("probe".to_string()), position: Some(7) });
+    server.set_parent(probe, Some(ship)).unwrap();
+    server.flush_queue();
+
+    let delta = Delta::between(&base, &server).unwrap();
+    assert_eq!(&[probe], delta.created());
+    assert_eq!(&[rock], delta.removed());
+    let positions = delta.components("position").unwrap();
+    assert_eq!(1, positions.added.len());
+    assert_eq!(1, positions.changed.len());
+    assert_eq!(vec![rock.index(), moon.index()], positions.removed);
+
+    // Send it over the wire.
+    let json = serde_json::to_string(&delta).unwrap();
+    let delta: Delta = serde_json::from_str(&json).unwrap();
+    client.apply_delta(&delta).unwrap();
+    assert!(Delta::between(&server, &client).unwrap().is_empty());
+    assert_eq!(Some(ship), client.parent(&probe));
+
+    // The systems only see what has a position now: the ship and the probe.
+    client.update();
+    assert_eq!(2, client.services.count);
+}
+
+#[test]
+fn test_history()
+{
+    let mut world = World::<Sync>::new();
+    world.create_entity(Parts { name: Some("ship".to_string()), position: Some(0) });
+    world.flush_queue();
+    let first = world.snapshot();
+    world.create_entity(Parts { name: Some("rock".to_string()), position: Some(1) });
+    world.flush_queue();
+    let second = world.snapshot();
+
+    assert!(Delta::between(&second, &world).unwrap().is_empty());
+    let step = Delta::between(&first, &second).unwrap();
+    assert!(!step.is_empty());
+
+    // Replaying the step from the first state gets back to the second.
+    world.rollback(&first);
+    world.apply_delta(&step).unwrap();
+    assert!(Delta::between(&second, &world).unwrap().is_empty());
+}
+
+pub struct Follows;
+
+#[test]
+fn test_apply_in_place()
+{
+    let mut server = World::<Sync>::new();
+    let ship = server.create_entity(Parts { name: Some("ship".to_string()), position: Some(0) });
+    let rock = server.create_entity(Parts { name: Some("rock".to_string()), position: Some(5) });
+    let moon = server.create_entity(Parts { name: Some("moon".to_string()), position: Some(9) });
+    server.flush_queue();
+    let base = server.snapshot();
+    let mut client: World<Sync> = serde_json::from_value(serde_json::to_value(&server).unwrap()).unwrap();
+    client.relate::<Follows>(moon, ship).unwrap();
+    client.relate::<Follows>(rock, ship).unwrap();
+    let tick = client.position.changes().tick();
+
+    server.with_entity_data(&ship, |e, c| c.position[e] = 3);
+    server.remove_entity(rock);
+    server.flush_queue();
+    client.apply_delta(&Delta::between(&base, &server).unwrap()).unwrap();
+
+    // Relations between surviving entities are kept, and the changes are tracked.
+    assert_eq!(&[moon], client.sources::<Follows>(&ship));
+    let changed: Vec<_> = client.position.changes().changed_since(tick).map(|e| **e).collect();
+    assert_eq!(vec![ship], changed);
+    assert_eq!(vec![rock], client.position.changes().removed_since(tick).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_recycled_index()
+{
+    let mut server = World::<Sync>::new();
+    server.create_entity(Parts { name: Some("ship".to_string()), position: Some(0) });
+    let rock = server.create_entity(Parts { name: Some("rock".to_string()), position: Some(5) });
+    server.flush_queue();
+    let base = server.snapshot();
+    let mut client: World<Sync> = serde_json::from_value(serde_json::to_value(&server).unwrap()).unwrap();
+
+    // The new entity takes the rock's index, and happens to have the same position.
+    server.remove_entity(rock);
+    server.flush_queue();
+    let probe = server.create_entity(Parts { name: Some("probe".to_string()), position: Some(5) });
+    server.flush_queue();
+    assert_eq!(rock.index(), probe.index());
+
+    let delta = Delta::between(&base, &server).unwrap();
+    let positions = delta.components("position").unwrap();
+    assert_eq!(vec![probe.index()], positions.added.iter().map(|&(i, _)| i).collect::<Vec<_>>());
+    assert_eq!(vec![rock.index()], positions.removed);
+    client.apply_delta(&delta).unwrap();
+    assert_eq!(Some(5), client.with_entity_data(&probe, |e, c| c.position[e]));
+    assert!(Delta::between(&server, &client).unwrap().is_empty());
+}
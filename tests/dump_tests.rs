@@ -0,0 +1,131 @@
+#![cfg(feature="serde")]
+
+#[macro_use]
+extern crate ecs;
+#[macro_use]
+extern crate serde_derive;
+extern crate ron;
+extern crate serde_json;
+
+use ecs::{Entity, World};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Position
+{
+    pub x: f32,
+    pub y: f32,
+}
+
+components! {
+    #[builder(Parts)]
+    #[derive(Serialize, Deserialize)]
+    struct Level {
+        #[hot] position: Position,
+        #[cold] name: String,
+    }
+}
+
+systems! {
+    struct NoSystems<Level, ()>;
+}
+
+fn name(world: &mut World<NoSystems>, entity: Entity) -> String
+{
+    world.with_entity_data(&entity, |e, c| c.name[e].clone()).unwrap()
+}
+
+fn level() -> (World<NoSystems>, Entity, Entity)
+{
+    let mut world = World::<NoSystems>::new();
+    let door = world.create_entity(Parts { name: Some("door".to_string()), position: Some(Position { x: 2.0, y: 0.0 }) });
+    let rubble = world.create_entity(Parts { name: Some("rubble".to_string()), position: Some(Position { x: 3.0, y: 0.0 }) });
+    let handle = world.create_entity(Parts { name: Some("handle".to_string()), ..Default::default() });
+    world.set_parent(handle, Some(door)).unwrap();
+    world.flush_queue();
+    world.remove_entity(rubble);
+    world.flush_queue();
+    (world, door, handle)
+}
+
+#[test]
+fn test_json()
+{
+    let (world, door, handle) = level();
+    let mut json = Vec::new();
+    world.dump(&mut serde_json::Serializer::pretty(&mut json)).unwrap();
+    let json = String::from_utf8(json).unwrap();
+
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(2, value["entities"].as_array().unwrap().len());
+    assert_eq!("door", value["entities"][0]["components"]["name"]);
+    assert_eq!(2.0, value["entities"][0]["components"]["position"]["x"]);
+    assert_eq!(serde_json::json!([0, 1]), value["entities"][1]["parent"]);
+    assert!(value["entities"][1]["components"].get("position").is_none());
+
+    let mut loaded = World::<NoSystems>::load_dump(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+    assert_eq!(2, loaded.entities().count());
+    assert_eq!("door", name(&mut loaded, door));
+    assert_eq!(Some(door), loaded.parent(&handle));
+    assert_eq!(Some(Position { x: 2.0, y: 0.0 }), loaded.with_entity_data(&door, |e, c| c.position[e].clone()));
+
+    // The removed entity's id stays dead.
+    let new = loaded.create_entity(Parts { name: Some("lever".to_string()), position: Some(Position { x: 4.0, y: 0.0 }) });
+    assert!(new.generation() > 1);
+}
+
+#[test]
+fn test_ron()
+{
+    let (world, door, handle) = level();
+    let mut text = Vec::new();
+    {
+        let mut serializer = ron::Serializer::new(&mut text, Some(ron::ser::PrettyConfig::new())).unwrap();
+        world.dump(&mut serializer).unwrap();
+    }
+    let text = String::from_utf8(text).unwrap();
+    let mut loaded = World::<NoSystems>::load_dump(&mut ron::Deserializer::from_str(&text).unwrap()).unwrap();
+    assert_eq!(2, loaded.entities().count());
+    assert_eq!("handle", name(&mut loaded, handle));
+    assert_eq!(vec![handle], loaded.children(&door).to_vec());
+}
+
+#[test]
+fn test_hand_written()
+{
+    let json = r#"{
+        "entities": [
+            { "id": [3, 1], "components": { "name": "chest", "position": { "x": 1.0, "y": 5.0 } } },
+            { "parent": [3, 1], "components": { "name": "key" } },
+            { "components": {} }
+        ],
+        "services": null
+    }"#;
+    let mut loaded = World::<NoSystems>::load_dump(&mut serde_json::Deserializer::from_str(json)).unwrap();
+    assert_eq!(3, loaded.entities().count());
+
+    let chest = loaded.entities().map(|e| **e).filter(|e| e.index() == 3).next().unwrap();
+    assert_eq!("chest", name(&mut loaded, chest));
+    let key = loaded.children(&chest)[0];
+    assert_eq!(4, key.index());
+    assert_eq!("key", name(&mut loaded, key));
+
+    // Unused indices below the highest are handed out first.
+    let new = loaded.create_entity(Parts { name: Some("coin".to_string()), position: Some(Position { x: 0.0, y: 0.0 }) });
+    assert_eq!(0, new.index());
+}
+
+#[test]
+fn test_unknown_component()
+{
+    let json = r#"{ "entities": [{ "components": { "colour": "red" } }], "services": null }"#;
+    let error = World::<NoSystems>::load_dump(&mut serde_json::Deserializer::from_str(json)).err().unwrap();
+    assert!(error.to_string().contains("colour"));
+}
+
+#[test]
+fn test_nil_id()
+{
+    let json = r#"{ "entities": [{ "id": [0, 0], "components": { "name": "ghost" } }], "services": null }"#;
+    let error = World::<NoSystems>::load_dump(&mut serde_json::Deserializer::from_str(json)).err().unwrap();
+    assert!(error.to_string().contains("generation 0"));
+}
@@ -0,0 +1,125 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{DataHelper, EventReader, Process, System, World};
+
+components! {
+    struct NoComponents;
+}
+
+#[derive(Default)]
+pub struct Log
+{
+    frame: u32,
+    damage: Vec<u32>,
+    sounds: Vec<u32>,
+}
+impl ecs::ServiceManager for Log {}
+
+#[derive(Debug, PartialEq)]
+pub struct Damage(u32);
+
+pub struct Attack;
+impl System for Attack { type Components = NoComponents; type Services = Log; }
+impl Process for Attack
+{
+    fn process(&mut self, data: &mut DataHelper<NoComponents, Log>)
+    {
+        data.services.frame += 1;
+        let frame = data.services.frame;
+        data.events::<Damage>().write(Damage(frame));
+        data.events::<Damage>().write(Damage(frame * 10));
+    }
+}
+
+pub struct Health(EventReader<Damage>);
+impl System for Health { type Components = NoComponents; type Services = Log; }
+impl Process for Health
+{
+    fn process(&mut self, data: &mut DataHelper<NoComponents, Log>)
+    {
+        let damage: Vec<u32> = data.read_events(&mut self.0).map(|d| d.0).collect();
+        data.services.damage.extend(damage);
+    }
+}
+
+/// Runs before the attack system, so it reads the previous update's events.
+pub struct Sound(EventReader<Damage>);
+impl System for Sound { type Components = NoComponents; type Services = Log; }
+impl Process for Sound
+{
+    fn process(&mut self, data: &mut DataHelper<NoComponents, Log>)
+    {
+        let damage: Vec<u32> = data.read_events(&mut self.0).map(|d| d.0).collect();
+        data.services.sounds.extend(damage);
+    }
+}
+
+systems! {
+    struct CombatSystems<NoComponents, Log> {
+        active: {
+            sound: Sound = Sound(EventReader::new()),
+            attack: Attack = Attack,
+            health: Health = Health(EventReader::new()),
+        },
+        passive: {}
+    }
+}
+
+#[test]
+fn test_events_read_once()
+{
+    let mut world = World::<CombatSystems>::new();
+    world.update();
+    world.update();
+    world.update();
+    assert_eq!(vec![1, 10, 2, 20, 3, 30], world.services.damage);
+    assert_eq!(vec![1, 10, 2, 20], world.services.sounds);
+}
+
+#[test]
+fn test_events_expire()
+{
+    let mut world = World::<CombatSystems>::new();
+    world.data.events::<Damage>().write(Damage(7));
+    let mut late = world.data.events::<Damage>().reader();
+    world.update();
+    assert_eq!(3, world.data.events::<Damage>().len());
+
+    // Events are dropped on the second update after they were written.
+    world.update();
+    assert_eq!(4, world.data.events::<Damage>().len());
+    let seen: Vec<_> = world.data.read_events(&mut late).map(|d| d.0).collect();
+    assert_eq!(vec![1, 10, 2, 20], seen);
+    let mut fresh: EventReader<Damage> = EventReader::new();
+    let seen: Vec<_> = world.data.read_events(&mut fresh).map(|d| d.0).collect();
+    assert_eq!(vec![1, 10, 2, 20], seen);
+    assert!(world.data.read_events::<u8>(&mut EventReader::new()).next().is_none());
+}
+
+#[test]
+fn test_world_is_send()
+{
+    let mut world = World::<CombatSystems>::new();
+    world.update();
+    let world = std::thread::spawn(move || { world.update(); world }).join().unwrap();
+    assert_eq!(vec![1, 10, 2, 20], world.services.damage);
+}
+
+#[test]
+fn test_reader_ahead()
+{
+    let mut busy = World::<CombatSystems>::new();
+    busy.update();
+    busy.update();
+    let mut reader = EventReader::new();
+    assert_eq!(4, busy.data.read_events::<Damage>(&mut reader).count());
+
+    // The reader is past the end of a quieter channel, so it waits for new events there.
+    let mut quiet = World::<CombatSystems>::new();
+    quiet.data.events::<Damage>().write(Damage(7));
+    assert_eq!(0, quiet.data.read_events(&mut reader).count());
+    quiet.data.events::<Damage>().write(Damage(8));
+    let seen: Vec<_> = quiet.data.read_events(&mut reader).map(|d| d.0).collect();
+    assert_eq!(vec![8], seen);
+}
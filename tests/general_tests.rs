@@ -141,3 +141,76 @@ fn test_general_1()
     world.systems.hello_world.0 = "Goodbye, World!";
     world.update();
 }
+
+#[test]
+fn test_stale_entity_handles()
+{
+    let mut world = World::<TestSystems>::new();
+
+    let old = world.create_entity(EntityInit {
+        team: Some(Team(1)),
+        ..Default::default()
+    });
+    world.remove_entity(old);
+    world.flush_queue();
+
+    // The freed index is reused, but under a new generation
+    let new = world.create_entity(EntityInit {
+        team: Some(Team(2)),
+        ..Default::default()
+    });
+    world.flush_queue();
+
+    assert_eq!(old.index(), new.index());
+    assert!(old.generation() != new.generation());
+    assert!(old != new && old.id() != new.id());
+
+    assert_eq!(None, world.with_entity_data(&old, |e, c| c.team[e]));
+    assert_eq!(Some(Team(2)), world.with_entity_data(&new, |e, c| c.team[e]));
+}
+
+#[test]
+fn test_fallible_entity_access()
+{
+    use ecs::EcsError;
+
+    let mut world = World::<TestSystems>::new();
+    let entity = world.create_entity(EntityInit {
+        position: Some(Position { x: 1.0, y: 2.0 }),
+        ..Default::default()
+    });
+    world.flush_queue();
+
+    assert_eq!(Err(EcsError::MissingComponent(entity)), world.try_with_entity_data(&entity, |e, c| c.team.try_index(&e).map(|t| *t)).unwrap());
+    assert_eq!(Ok(Position { x: 1.0, y: 2.0 }), world.try_with_entity_data(&entity, |e, c| c.position[e]));
+
+    world.remove_entity(entity);
+    assert_eq!(Err(EcsError::PendingRemoval(entity)), world.try_modify_entity(entity, ()));
+    assert_eq!(Err(EcsError::PendingRemoval(entity)), world.try_remove_entity(entity));
+    world.remove_entity(entity);
+    world.flush_queue();
+
+    assert_eq!(Err(EcsError::NoSuchEntity(entity)), world.try_modify_entity(entity, ()));
+    assert_eq!(Err(EcsError::NoSuchEntity(entity)), world.try_with_entity_data(&entity, |_, _| ()));
+    assert!(!world.is_valid(&entity));
+}
+
+#[test]
+fn test_deterministic_iteration()
+{
+    let mut world = World::<TestSystems>::new();
+    let entities: Vec<_> = (0..6).map(|_| world.create_entity(EntityInit {
+        position: Some(Position { x: 0.0, y: 0.0 }),
+        feature: Some(SomeFeature),
+        ..Default::default()
+    })).collect();
+    world.remove_entity(entities[1]);
+    world.remove_entity(entities[4]);
+    world.flush_queue();
+    world.create_entity(());
+    world.create_entity(());
+
+    // Entities are always visited by index, whatever order they were created in.
+    let indices: Vec<_> = world.entities().map(|e| e.index()).collect();
+    assert_eq!(vec![0, 1, 2, 3, 4, 5], indices);
+}
@@ -0,0 +1,70 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{EcsError, Entity, EntityData, System, World};
+
+components! {
+    struct NoComponents;
+}
+
+/// Records the order in which entities are deactivated.
+#[derive(Default)]
+pub struct Graveyard(Vec<Entity>);
+impl System for Graveyard
+{
+    type Components = NoComponents;
+    type Services = ();
+    fn deactivated(&mut self, entity: &EntityData<NoComponents>, _: &NoComponents, _: &mut ())
+    {
+        self.0.push(***entity);
+    }
+}
+
+systems! {
+    struct HierarchySystems<NoComponents, ()> {
+        active: {},
+        passive: {
+            graveyard: Graveyard = Graveyard::default(),
+        }
+    }
+}
+
+#[test]
+fn test_hierarchy()
+{
+    let mut world = World::<HierarchySystems>::new();
+    let body = world.create_entity(());
+    let arm = world.create_entity(());
+    let hand = world.create_entity(());
+    let sword = world.create_entity(());
+    let leg = world.create_entity(());
+    world.set_parent(arm, Some(body)).unwrap();
+    world.set_parent(hand, Some(arm)).unwrap();
+    world.set_parent(sword, Some(hand)).unwrap();
+    world.set_parent(leg, Some(body)).unwrap();
+
+    assert_eq!(Some(hand), world.parent(&sword));
+    assert_eq!(None, world.parent(&body));
+    assert_eq!(&[arm, leg], world.children(&body));
+    assert_eq!(vec![arm, hand, sword, leg], world.descendants(&body).collect::<Vec<_>>());
+
+    // No entity can become its own ancestor.
+    assert_eq!(Err(EcsError::HierarchyCycle(body)), world.set_parent(body, Some(sword)));
+    assert_eq!(Err(EcsError::HierarchyCycle(arm)), world.set_parent(arm, Some(arm)));
+
+    // Reparenting moves the whole subtree.
+    world.set_parent(hand, Some(leg)).unwrap();
+    assert_eq!(&[hand], world.children(&leg));
+    assert!(world.children(&arm).is_empty());
+    world.set_parent(hand, Some(arm)).unwrap();
+
+    // Removal cascades, children first.
+    world.remove_entity(arm);
+    assert_eq!(Err(EcsError::PendingRemoval(sword)), world.set_parent(sword, None));
+    world.flush_queue();
+    assert!(!world.is_valid(&sword));
+    assert_eq!(vec![sword, hand, arm], world.systems.graveyard.0);
+    assert_eq!(vec![body, leg], world.entities().map(|e| **e).collect::<Vec<_>>());
+    assert_eq!(&[leg], world.children(&body));
+    assert_eq!(None, world.parent(&sword));
+}
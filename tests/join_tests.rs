@@ -0,0 +1,49 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{Join, World};
+
+components! {
+    #[builder(Parts)]
+    struct Body {
+        #[hot] position: i32,
+        #[hot] velocity: i32,
+        #[cold] mass: u32,
+    }
+}
+
+systems! {
+    struct NoSystems<Body, ()>;
+}
+
+#[test]
+fn test_join()
+{
+    let mut world = World::<NoSystems>::new();
+    let moving = world.create_entity(Parts { position: Some(0), velocity: Some(2), mass: Some(3) });
+    world.create_entity(Parts { position: Some(10), mass: Some(1), ..Default::default() });
+    world.create_entity(Parts { velocity: Some(7), ..Default::default() });
+    let light = world.create_entity(Parts { position: Some(5), velocity: Some(-1), ..Default::default() });
+
+    let tick = world.position.changes().tick();
+    {
+        let c = &mut world.data.components;
+        for (position, velocity) in (&mut c.position, &c.velocity).join()
+        {
+            *position += *velocity;
+        }
+    }
+    let positions: Vec<_> = world.entities().map(|e| world.position.get(&e)).collect();
+    assert_eq!(vec![Some(2), Some(10), None, Some(4)], positions);
+
+    // Mutable joins count as changes.
+    let changed: Vec<_> = world.position.changes().changed_since(tick).map(|e| **e).collect();
+    assert_eq!(vec![moving, light], changed);
+
+    // Mixing hot and cold lists, driven by the smaller cold one.
+    let c = &world.data.components;
+    let mut heavy: Vec<_> = (&c.mass, &c.position, &c.velocity).join().map(|(m, p, v)| (*m, *p, *v)).collect();
+    heavy.sort();
+    assert_eq!(vec![(3, 2, 2)], heavy);
+    assert_eq!(2, (&c.mass, &c.position).join().count());
+}
@@ -0,0 +1,122 @@
+#![cfg(feature="serde")]
+
+#[macro_use]
+extern crate ecs;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use ecs::{Entity, EntityMap, MapEntities, ModifyData, World};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Target(Entity);
+
+impl MapEntities for Target
+{
+    fn map_entities(&mut self, map: &EntityMap)
+    {
+        self.0.map_entities(map);
+    }
+}
+
+/// Entity ids in components without `MapEntities` are left alone.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Raw(Entity);
+
+components! {
+    #[builder(Parts)]
+    #[derive(Serialize, Deserialize)]
+    struct Level {
+        #[hot] name: String,
+        #[cold] target: Target,
+        #[cold] route: Vec<Entity>,
+        #[cold] raw: Raw,
+    }
+}
+
+systems! {
+    struct NoSystems<Level, ()>;
+}
+
+fn name(world: &mut World<NoSystems>, entity: Entity) -> String
+{
+    world.with_entity_data(&entity, |e, c| c.name[e].clone()).unwrap()
+}
+
+#[test]
+fn test_merge()
+{
+    let mut chunk = World::<NoSystems>::new();
+    let gate = chunk.create_entity(Parts { name: Some("gate".to_string()), ..Default::default() });
+    let guard = chunk.create_entity(Parts { name: Some("guard".to_string()), ..Default::default() });
+    let sword = chunk.create_entity(Parts { name: Some("sword".to_string()), ..Default::default() });
+    chunk.modify_entity(guard, move |e: ModifyData<Level>, c: &mut Level| {
+        c.target.insert(&e, Target(gate));
+        c.route.insert(&e, vec![gate, guard]);
+        c.raw.insert(&e, Raw(gate));
+    });
+    chunk.set_parent(sword, Some(guard)).unwrap();
+    chunk.flush_queue();
+    let json = serde_json::to_string(&chunk).unwrap();
+
+    let mut world = World::<NoSystems>::new();
+    let player = world.create_entity(Parts { name: Some("player".to_string()), ..Default::default() });
+    world.flush_queue();
+
+    // Load the chunk twice, so both copies need their own ids.
+    let first = world.merge_saved(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+    let second = world.merge_saved(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+    world.flush_queue();
+    assert_eq!(7, world.entities().count());
+    assert_eq!(3, first.len());
+
+    for map in &[&first, &second]
+    {
+        let (gate, guard, sword) = (map.map(gate), map.map(guard), map.map(sword));
+        assert!(gate != player && world.is_valid(&gate));
+        assert_eq!("gate", name(&mut world, gate));
+        assert_eq!("sword", name(&mut world, sword));
+        assert_eq!(Some(guard), world.parent(&sword));
+        world.with_entity_data(&guard, |e, c| {
+            assert_eq!(Target(gate), c.target[e]);
+            assert_eq!(vec![gate, guard], c.route[e]);
+        });
+    }
+
+    // The raw id was copied unchanged.
+    let guard = first.map(guard);
+    assert_eq!(Some(Raw(gate)), world.with_entity_data(&guard, |e, c| c.raw[e].clone()));
+}
+
+#[test]
+fn test_unknown_ids()
+{
+    let mut chunk = World::<NoSystems>::new();
+    let gate = chunk.create_entity(Parts { name: Some("gate".to_string()), ..Default::default() });
+    let lost = chunk.create_entity(Parts { name: Some("lost".to_string()), ..Default::default() });
+    chunk.flush_queue();
+    chunk.remove_entity(lost);
+    chunk.modify_entity(gate, move |e: ModifyData<Level>, c: &mut Level| {
+        c.target.insert(&e, Target(lost));
+        c.route.insert(&e, vec![gate, lost]);
+    });
+    chunk.flush_queue();
+    let json = serde_json::to_string(&chunk).unwrap();
+
+    // The world has a live entity with the same id as the one that didn't make it into the save.
+    let mut world = World::<NoSystems>::new();
+    world.create_entity(Parts { name: Some("player".to_string()), ..Default::default() });
+    let camera = world.create_entity(Parts { name: Some("camera".to_string()), ..Default::default() });
+    world.flush_queue();
+    assert_eq!(lost, camera);
+
+    let map = world.merge_saved(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+    world.flush_queue();
+    assert!(map.get(&lost).is_none());
+    assert_eq!(Entity::nil(), map.map(lost));
+    let gate = map.map(gate);
+    world.with_entity_data(&gate, |e, c| {
+        assert_eq!(Target(Entity::nil()), c.target[e]);
+        assert_eq!(vec![gate, Entity::nil()], c.route[e]);
+    });
+}
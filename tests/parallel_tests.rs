@@ -0,0 +1,166 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{ComponentList, EcsError, System, SystemManager, World};
+use ecs::storage::ColdStorage;
+use ecs::system::{ComponentAccess, ParallelProcess, Schedule, SystemDesc};
+
+components! {
+    #[builder(Parts)]
+    struct Body {
+        #[hot] position: i32,
+        #[hot] velocity: i32,
+        #[cold] health: u32,
+    }
+}
+
+pub struct Movement;
+impl System for Movement { type Components = Body; type Services = (); }
+impl ParallelProcess for Movement
+{
+    fn process(&mut self, mut access: ComponentAccess<Body>, _: &())
+    {
+        let velocity: &ComponentList<Body, i32> = access.read("velocity");
+        let position: &mut ComponentList<Body, i32> = access.write("position");
+        for e in access.entities()
+        {
+            if velocity.has(&e) && position.has(&e)
+            {
+                position[e] += velocity[e];
+            }
+        }
+    }
+}
+
+pub struct Regen;
+impl System for Regen { type Components = Body; type Services = (); }
+impl ParallelProcess for Regen
+{
+    fn process(&mut self, mut access: ComponentAccess<Body>, _: &())
+    {
+        let health: &mut ComponentList<Body, u32, ColdStorage<u32>> = access.write("health");
+        for e in access.entities()
+        {
+            if let Some(health) = health.borrow(&e)
+            {
+                *health += 1;
+            }
+        }
+    }
+}
+
+pub struct Falling;
+impl System for Falling { type Components = Body; type Services = (); }
+impl ParallelProcess for Falling
+{
+    fn process(&mut self, mut access: ComponentAccess<Body>, _: &())
+    {
+        let position: &ComponentList<Body, i32> = access.read("position");
+        let health: &mut ComponentList<Body, u32, ColdStorage<u32>> = access.write("health");
+        for e in access.entities()
+        {
+            if position.has(&e) && position[e] < 0
+            {
+                health.borrow(&e).map(|health| *health = 0);
+            }
+        }
+    }
+}
+
+systems! {
+    struct BodySystems<Body, ()> {
+        active: {
+            movement: Movement = Movement => [reads(velocity), writes(position)],
+            regen: Regen = Regen => [writes(health)],
+            falling: Falling = Falling => [reads(position), writes(health)],
+        },
+        passive: {}
+    }
+}
+
+fn spawn(world: &mut World<BodySystems>)
+{
+    world.create_entity(Parts { position: Some(0), velocity: Some(2), health: Some(10) });
+    world.create_entity(Parts { position: Some(1), velocity: Some(-3), health: Some(5) });
+    world.create_entity(Parts { velocity: Some(1), ..Default::default() });
+}
+
+fn state(world: &World<BodySystems>) -> Vec<(Option<i32>, Option<u32>)>
+{
+    world.entities().map(|e| (world.position.get(&e), world.health.get(&e))).collect()
+}
+
+#[test]
+fn test_parallel_stages()
+{
+    let schedule = Schedule::new(vec![
+        SystemDesc::new("movement").reads(&["velocity"]).writes(&["position"]),
+        SystemDesc::new("regen").writes(&["health"]),
+        SystemDesc::new("falling").reads(&["position"]).writes(&["health"]),
+        SystemDesc::new("log"),
+        SystemDesc::new("late").writes(&["sprite"]).after(&["log"]),
+    ]).unwrap();
+    assert_eq!(vec![vec!["movement", "regen"], vec!["falling"], vec!["log"], vec!["late"]], schedule.stages());
+}
+
+#[test]
+fn test_parallel_matches_sequential()
+{
+    let mut sequential = World::<BodySystems>::new();
+    let mut parallel = World::<BodySystems>::new();
+    spawn(&mut sequential);
+    spawn(&mut parallel);
+
+    for _ in 0..3
+    {
+        sequential.update();
+        parallel.update_parallel();
+        assert_eq!(state(&sequential), state(&parallel));
+    }
+    assert_eq!(vec![(Some(6), Some(13)), (Some(-8), Some(0)), (None, None)], state(&parallel));
+
+    // Parallel systems can still be processed on their own.
+    process!(parallel, movement);
+    assert_eq!(Some(8), parallel.position.get(&parallel.entities().next().unwrap()));
+}
+
+/// Declares what it writes, but only implements `Process`, so it can't share a stage.
+pub struct Tally;
+impl System for Tally { type Components = Body; type Services = (); }
+impl ecs::Process for Tally
+{
+    fn process(&mut self, _: &mut ecs::DataHelper<Body, ()>) {}
+}
+
+systems! {
+    struct MixedSystems<Body, ()> {
+        active: {
+            movement: Movement = Movement => [reads(velocity), writes(position)],
+            tally: Tally = Tally => [writes(health)],
+        },
+        passive: {}
+    }
+}
+
+#[test]
+fn test_sequential_system_stage()
+{
+    let mut world = World::<MixedSystems>::new();
+    let stages = world.systems.__scheduled().unwrap().0.stages();
+    assert_eq!(vec![vec!["movement"], vec!["tally"]], stages);
+}
+
+systems! {
+    struct MisspeltSystems<Body, ()> {
+        active: {
+            movement: Movement = Movement => [reads(velocity), writes(postion)],
+        },
+        passive: {}
+    }
+}
+
+#[test]
+fn test_unknown_list()
+{
+    assert_eq!(EcsError::UnknownComponent("postion"), World::<MisspeltSystems>::try_new().err().unwrap());
+}
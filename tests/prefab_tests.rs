@@ -0,0 +1,60 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{BuildData, Prefab, World};
+
+components! {
+    struct Ship {
+        #[hot] health: i32,
+        #[hot] position: (i32, i32),
+        #[cold] name: String,
+        #[cold] damage: u32,
+    }
+}
+
+systems! {
+    struct NoSystems<Ship, ()>;
+}
+
+#[test]
+fn test_prefabs()
+{
+    let mut world = World::<NoSystems>::new();
+    let gunboat = Prefab::new("gunboat")
+        .with(|c: &mut Ship| &mut c.health, 100)
+        .with(|c: &mut Ship| &mut c.name, "Gunboat".to_string())
+        .child(Prefab::new("turret").with(|c: &mut Ship| &mut c.damage, 5))
+        .child(Prefab::new("turret").with(|c: &mut Ship| &mut c.damage, 5)
+            .child(Prefab::new("scope").with(|c: &mut Ship| &mut c.health, 1)));
+    assert_eq!("gunboat", gunboat.name());
+    assert!(gunboat.find("scope").is_some());
+    assert!(gunboat.find("engine").is_none());
+
+    let plain = world.spawn_prefab(&gunboat, ());
+    let flagship = world.spawn_prefab(&gunboat, |e: BuildData<Ship>, c: &mut Ship| {
+        c.name.add(&e, "Flagship".to_string());
+        c.position.add(&e, (3, 4));
+    });
+    world.flush_queue();
+    assert_eq!(8, world.entities().count());
+
+    assert_eq!(Some(("Gunboat".to_string(), None)),
+               world.with_entity_data(&plain, |e, c| (c.name[e].clone(), c.position.get(&e))));
+    assert_eq!(Some(("Flagship".to_string(), 100, (3, 4))),
+               world.with_entity_data(&flagship, |e, c| (c.name[e].clone(), c.health[e], c.position[e])));
+
+    // The overrides only apply to the root entity.
+    let turrets = world.children(&flagship).to_vec();
+    assert_eq!(2, turrets.len());
+    for turret in &turrets
+    {
+        assert_eq!(Some(5), world.with_entity_data(turret, |e, c| c.damage[e]));
+        assert_eq!(None, world.with_entity_data(turret, |e, c| c.name.get(&e)).unwrap());
+        assert_eq!(None, world.with_entity_data(turret, |e, c| c.position.get(&e)).unwrap());
+    }
+    let scope = world.children(&turrets[1])[0];
+    assert_eq!(Some(1), world.with_entity_data(&scope, |e, c| c.health[e]));
+
+    // Instances don't share children.
+    assert!(world.children(&plain).iter().all(|t| !turrets.contains(t)));
+}
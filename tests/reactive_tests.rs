@@ -0,0 +1,86 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{DataHelper, Entity, ModifyData, System, World};
+use ecs::system::{ReactiveBatch, ReactiveProcess, ReactiveSystem};
+
+components! {
+    #[builder(Parts)]
+    struct Body {
+        #[hot] position: i32,
+        #[hot] velocity: i32,
+    }
+}
+
+#[derive(Default)]
+pub struct Log
+{
+    entered: Vec<Entity>,
+    modified: Vec<Entity>,
+    left: Vec<Entity>,
+    runs: usize,
+}
+impl ecs::ServiceManager for Log {}
+
+pub struct Mirror;
+impl System for Mirror { type Components = Body; type Services = Log; }
+impl ReactiveProcess for Mirror
+{
+    fn process(&mut self, batch: ReactiveBatch<Body>, data: &mut DataHelper<Body, Log>)
+    {
+        data.services.runs += 1;
+        data.services.entered = batch.entered().map(|e| **e).collect();
+        data.services.modified = batch.modified().map(|e| **e).collect();
+        data.services.left = batch.left().to_vec();
+        // Touching a watched list from inside the system is not reported back to it.
+        for e in batch.modified()
+        {
+            data.components.position[e] += 0;
+        }
+    }
+}
+
+systems! {
+    struct BodySystems<Body, Log> {
+        active: {
+            sync: ReactiveSystem<Mirror> = ReactiveSystem::new(Mirror, aspect!(<Body> all: [position]))
+                .watch(|c| c.position.changes()),
+        },
+        passive: {}
+    }
+}
+
+#[test]
+fn test_reactive_batches()
+{
+    let mut world = World::<BodySystems>::new();
+    let first = world.create_entity(Parts { position: Some(0), ..Default::default() });
+    let second = world.create_entity(Parts { position: Some(0), ..Default::default() });
+    let ignored = world.create_entity(Parts { velocity: Some(1), ..Default::default() });
+
+    world.update();
+    assert_eq!(vec![first, second], world.services.entered);
+    assert!(world.services.modified.is_empty());
+
+    // Nothing happened, so the system doesn't run.
+    world.update();
+    assert_eq!(1, world.services.runs);
+
+    world.with_entity_data(&second, |e, c| c.position[e] = 5);
+    world.with_entity_data(&ignored, |e, c| c.velocity[e] = 5);
+    world.update();
+    assert_eq!(2, world.services.runs);
+    assert!(world.services.entered.is_empty());
+    assert_eq!(vec![second], world.services.modified);
+
+    world.modify_entity(first, |e: ModifyData<Body>, c: &mut Body| { c.position.remove(&e); });
+    world.remove_entity(second);
+    world.modify_entity(ignored, |e: ModifyData<Body>, c: &mut Body| { c.position.insert(&e, 1); });
+    world.update();
+    assert_eq!(vec![ignored], world.services.entered);
+    assert!(world.services.modified.is_empty());
+    assert_eq!(vec![first, second], world.services.left);
+
+    world.update();
+    assert_eq!(3, world.services.runs);
+}
@@ -0,0 +1,97 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{DataHelper, EventReader, Process, System, Unlinked, World};
+
+components! {
+    struct NoComponents;
+}
+
+pub struct Targets;
+pub struct DockedAt;
+
+/// Counts the turrets that lost their target.
+#[derive(Default)]
+pub struct Retarget(EventReader<Unlinked<Targets>>, Vec<(ecs::Entity, ecs::Entity)>);
+impl System for Retarget { type Components = NoComponents; type Services = (); }
+impl Process for Retarget
+{
+    fn process(&mut self, data: &mut DataHelper<NoComponents, ()>)
+    {
+        let lost: Vec<_> = data.read_events(&mut self.0).map(|u| (u.source, u.target)).collect();
+        self.1.extend(lost);
+    }
+}
+
+systems! {
+    struct RelationSystems<NoComponents, ()> {
+        active: {
+            retarget: Retarget = Retarget::default(),
+        },
+        passive: {}
+    }
+}
+
+#[test]
+fn test_relations()
+{
+    let mut world = World::<RelationSystems>::new();
+    let ship = world.create_entity(());
+    let station = world.create_entity(());
+    let turrets: Vec<_> = (0..3).map(|_| world.create_entity(())).collect();
+    for &turret in &turrets
+    {
+        assert_eq!(Ok(true), world.relate::<Targets>(turret, ship));
+    }
+    assert_eq!(Ok(false), world.relate::<Targets>(turrets[0], ship));
+    world.relate::<Targets>(turrets[0], station).unwrap();
+    world.relate::<DockedAt>(ship, station).unwrap();
+
+    assert_eq!(&turrets[..], world.sources::<Targets>(&ship));
+    assert_eq!(&[ship, station], world.targets::<Targets>(&turrets[0]));
+    assert_eq!(&[ship], world.sources::<DockedAt>(&station));
+    assert!(world.sources::<DockedAt>(&ship).is_empty());
+
+    assert!(world.unrelate::<Targets>(&turrets[1], &ship));
+    assert!(!world.unrelate::<Targets>(&turrets[1], &ship));
+    assert_eq!(&[turrets[0], turrets[2]], world.sources::<Targets>(&ship));
+
+    // Removing a source drops its edges quietly, removing a target notifies the sources.
+    world.remove_entity(turrets[2]);
+    world.remove_entity(ship);
+    world.update();
+    assert_eq!(vec![(turrets[0], ship)], world.systems.retarget.1);
+    assert_eq!(&[station], world.targets::<Targets>(&turrets[0]));
+    assert!(world.sources::<DockedAt>(&station).is_empty());
+    assert!(world.sources::<Targets>(&ship).is_empty());
+
+    // A new entity reusing the index starts without relations.
+    let reused = world.create_entity(());
+    assert!(world.sources::<Targets>(&reused).is_empty());
+    assert!(world.relate::<Targets>(turrets[2], reused).is_err());
+}
+
+#[test]
+fn test_merge()
+{
+    let mut other = World::<RelationSystems>::new();
+    let ship = other.create_entity(());
+    let turret = other.create_entity(());
+    let doomed = other.create_entity(());
+    other.relate::<Targets>(turret, ship).unwrap();
+    other.relate::<DockedAt>(ship, doomed).unwrap();
+    other.flush_queue();
+    other.remove_entity(doomed);
+
+    let mut world = World::<RelationSystems>::new();
+    let station = world.create_entity(());
+    world.flush_queue();
+    let map = world.data.merge(other.data);
+    world.flush_queue();
+
+    let (ship, turret) = (map.map(ship), map.map(turret));
+    assert_eq!(&[ship], world.targets::<Targets>(&turret));
+    assert_eq!(&[turret], world.sources::<Targets>(&ship));
+    assert!(world.targets::<DockedAt>(&ship).is_empty());
+    assert!(world.sources::<DockedAt>(&station).is_empty());
+}
@@ -0,0 +1,185 @@
+#![cfg(feature="serde")]
+
+#[macro_use]
+extern crate ecs;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use ecs::{BuildData, Entity, EntityMap, MapEntities, ModifyData, World};
+use ecs::replication::{Client, Packet, Server};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Target(Entity);
+
+impl MapEntities for Target
+{
+    fn map_entities(&mut self, map: &EntityMap)
+    {
+        self.0.map_entities(map);
+    }
+}
+
+components! {
+    #[derive(Serialize, Deserialize)]
+    struct Game {
+        #[hot] position: (i32, i32),
+        #[cold] target: Target,
+        #[cold] secret: String,
+    }
+}
+
+systems! {
+    struct NoSystems<Game, ()>;
+}
+
+fn server() -> Server
+{
+    Server::new().replicate("position").replicate("target")
+}
+
+/// Sends a packet through bytes, as a real transport would.
+fn send(packet: Packet) -> Packet
+{
+    let bytes = serde_json::to_vec(&packet).unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+fn position(world: &mut World<NoSystems>, entity: Entity) -> Option<(i32, i32)>
+{
+    world.with_entity_data(&entity, |e, c| c.position.get(&e)).unwrap()
+}
+
+#[test]
+fn test_replicate()
+{
+    let mut world = World::<NoSystems>::new();
+    let ship = world.create_entity(|e: BuildData<Game>, c: &mut Game| {
+        c.position.add(&e, (0, 0));
+        c.secret.add(&e, "cargo".to_string());
+    });
+    let rock = world.create_entity(|e: BuildData<Game>, c: &mut Game| { c.position.add(&e, (5, 5)); });
+    world.modify_entity(rock, move |e: ModifyData<Game>, c: &mut Game| { c.target.insert(&e, Target(ship)); });
+    world.flush_queue();
+
+    // The client has an entity of its own, so its ids differ from the server's.
+    let mut local = World::<NoSystems>::new();
+    let hud = local.create_entity(());
+    local.flush_queue();
+
+    let mut server = server();
+    let id = server.connect();
+    let mut client = Client::new();
+    client.apply(&mut local, send(server.update(&world, id).unwrap())).unwrap();
+    local.flush_queue();
+    assert_eq!(3, local.entities().count());
+
+    let local_ship = client.local(ship).unwrap();
+    let local_rock = client.local(rock).unwrap();
+    assert!(local_ship != ship && local_ship != hud);
+    assert_eq!(Some((0, 0)), position(&mut local, local_ship));
+    assert_eq!(Some(Target(local_ship)), local.with_entity_data(&local_rock, |e, c| c.target[e].clone()));
+    assert_eq!(Some(false), local.with_entity_data(&local_ship, |e, c| c.secret.has(&e)));
+
+    // Nothing changed, so there's nothing to send.
+    assert!(server.update(&world, id).unwrap().is_empty());
+
+    world.with_entity_data(&ship, |e, c| c.position[e] = (1, 0));
+    world.remove_entity(rock);
+    let probe = world.create_entity(|e: BuildData<Game>, c: &mut Game| { c.position.add(&e, (9, 9)); });
+    world.flush_queue();
+
+    let packet = server.update(&world, id).unwrap();
+    assert_eq!(&[probe], packet.spawned());
+    assert_eq!(&[rock], packet.despawned());
+    client.apply(&mut local, send(packet)).unwrap();
+    local.flush_queue();
+    assert_eq!(3, local.entities().count());
+    assert!(client.local(rock).is_none());
+    assert!(!local.is_valid(&local_rock));
+    assert_eq!(Some((1, 0)), position(&mut local, local_ship));
+    assert_eq!(Some((9, 9)), position(&mut local, client.local(probe).unwrap()));
+
+    world.modify_entity(ship, |e: ModifyData<Game>, c: &mut Game| { c.position.remove(&e); });
+    world.flush_queue();
+    client.apply(&mut local, send(server.update(&world, id).unwrap())).unwrap();
+    assert_eq!(None, position(&mut local, local_ship));
+}
+
+#[test]
+fn test_late_join()
+{
+    let mut world = World::<NoSystems>::new();
+    let mut server = server();
+    let first = server.connect();
+    world.create_entity(|e: BuildData<Game>, c: &mut Game| { c.position.add(&e, (1, 1)); });
+    world.flush_queue();
+    server.update(&world, first).unwrap();
+
+    // A client joining later is sent everything, and pending entities wait for a flush.
+    world.create_entity(|e: BuildData<Game>, c: &mut Game| { c.position.add(&e, (2, 2)); });
+    let second = server.connect();
+    let packet = server.update(&world, second).unwrap();
+    assert_eq!(1, packet.spawned().len());
+    assert_eq!(1, packet.components("position").unwrap().added.len());
+    assert!(packet.components("secret").is_none());
+
+    server.disconnect(first);
+    assert!(server.update(&world, first).is_err());
+}
+
+#[test]
+fn test_recycled_index()
+{
+    let mut world = World::<NoSystems>::new();
+    let rock = world.create_entity(|e: BuildData<Game>, c: &mut Game| { c.position.add(&e, (5, 5)); });
+    world.flush_queue();
+    let mut server = server();
+    let id = server.connect();
+    let mut local = World::<NoSystems>::new();
+    let mut client = Client::new();
+    client.apply(&mut local, send(server.update(&world, id).unwrap())).unwrap();
+    local.flush_queue();
+
+    // The probe takes the rock's index, with an equal position, so it has to be sent in full.
+    world.remove_entity(rock);
+    world.flush_queue();
+    let probe = world.create_entity(|e: BuildData<Game>, c: &mut Game| { c.position.add(&e, (5, 5)); });
+    world.flush_queue();
+    assert_eq!(rock.index(), probe.index());
+
+    let packet = server.update(&world, id).unwrap();
+    assert_eq!(1, packet.components("position").unwrap().added.len());
+    client.apply(&mut local, send(packet)).unwrap();
+    local.flush_queue();
+    assert_eq!(1, local.entities().count());
+    assert_eq!(Some((5, 5)), position(&mut local, client.local(probe).unwrap()));
+}
+
+#[test]
+fn test_removed_locally()
+{
+    let mut world = World::<NoSystems>::new();
+    let ship = world.create_entity(|e: BuildData<Game>, c: &mut Game| { c.position.add(&e, (0, 0)); });
+    let rock = world.create_entity(|e: BuildData<Game>, c: &mut Game| { c.position.add(&e, (5, 5)); });
+    world.flush_queue();
+    let mut server = server();
+    let id = server.connect();
+    let mut local = World::<NoSystems>::new();
+    let mut client = Client::new();
+    client.apply(&mut local, send(server.update(&world, id).unwrap())).unwrap();
+    local.flush_queue();
+
+    // The client removes the rock, and queues the ship's removal, before the server changes them.
+    local.remove_entity(client.local(rock).unwrap());
+    local.flush_queue();
+    local.remove_entity(client.local(ship).unwrap());
+    world.modify_entity(rock, |e: ModifyData<Game>, c: &mut Game| { c.position[e] = (6, 6); });
+    world.modify_entity(ship, |e: ModifyData<Game>, c: &mut Game| { c.position.remove(&e); });
+    world.flush_queue();
+
+    client.apply(&mut local, send(server.update(&world, id).unwrap())).unwrap();
+    local.flush_queue();
+    assert_eq!(None, client.local(rock));
+    assert_eq!(0, local.entities().count());
+}
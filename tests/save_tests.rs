@@ -0,0 +1,120 @@
+#![cfg(feature="serde")]
+
+#[macro_use]
+extern crate ecs;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use ecs::{BuildData, World};
+use ecs::save::{Schema, Value};
+
+/// The components as they were in the first release.
+mod old
+{
+    components! {
+        #[derive(Serialize, Deserialize)]
+        struct Body {
+            #[hot] position: (f32, f32),
+            #[cold] name: String,
+            #[cold] speed: u32,
+        }
+    }
+
+    systems! {
+        struct Systems<Body, ()>;
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Position
+{
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+/// Position gained a z coordinate, names became upper case, speed was dropped and health added.
+mod new
+{
+    use Position;
+
+    components! {
+        #[derive(Serialize, Deserialize)]
+        struct Body {
+            #[hot] position: Position,
+            #[cold] name: String,
+            #[hot] health: i32,
+        }
+    }
+
+    systems! {
+        struct Systems<Body, ()>;
+    }
+}
+
+fn old_save() -> String
+{
+    let mut world = World::<old::Systems>::new();
+    world.create_entity(|e: BuildData<old::Body>, c: &mut old::Body| {
+        c.position.add(&e, (1.0, 2.0));
+        c.name.add(&e, "ship".to_string());
+        c.speed.add(&e, 4);
+    });
+    world.flush_queue();
+    let mut json = Vec::new();
+    world.save_versioned(&Schema::new(), &mut serde_json::Serializer::new(&mut json)).unwrap();
+    String::from_utf8(json).unwrap()
+}
+
+fn schema() -> Schema
+{
+    Schema::new()
+        .version("position", 1)
+        .convert("position", 0, |(x, y): (f32, f32)| Position { x: x, y: y, z: 0.0 })
+        .version("name", 2)
+        .upgrade("name", 0, |name| Ok(name))
+        .upgrade("name", 1, |name| match name {
+            Value::String(name) => Ok(Value::String(name.to_uppercase())),
+            _ => Err("names should be strings".to_string()),
+        })
+        .version("health", 0)
+}
+
+#[test]
+fn test_upgrade()
+{
+    let json = old_save();
+    let mut world: World<new::Systems> =
+        World::load_versioned(&schema(), &mut serde_json::Deserializer::from_str(&json)).unwrap();
+    let ship = world.entities().map(|e| **e).next().unwrap();
+    world.with_entity_data(&ship, |e, c| {
+        assert_eq!(Position { x: 1.0, y: 2.0, z: 0.0 }, c.position[e]);
+        assert_eq!("SHIP", c.name[e]);
+        assert!(!c.health.has(&e));
+    });
+
+    // Saving again records the new versions, so nothing is upgraded twice.
+    let mut json = Vec::new();
+    world.save_versioned(&schema(), &mut serde_json::Serializer::new(&mut json)).unwrap();
+    let mut world: World<new::Systems> =
+        World::load_versioned(&schema(), &mut serde_json::Deserializer::from_slice(&json)).unwrap();
+    assert_eq!(Some("SHIP".to_string()), world.with_entity_data(&ship, |e, c| c.name[e].clone()));
+}
+
+#[test]
+fn test_upgrade_errors()
+{
+    let json = old_save();
+    let load = |schema: Schema, json: &str| {
+        World::<new::Systems>::load_versioned(&schema, &mut serde_json::Deserializer::from_str(json)).err().unwrap().to_string()
+    };
+
+    let missing = Schema::new().version("position", 1).version("health", 0);
+    assert!(load(missing, &json).contains("no upgrade for `position` from version 0"));
+
+    let newer = json.replace("[\"name\",0]", "[\"name\",3]");
+    assert!(load(schema(), &newer).contains("`name` was saved at version 3"));
+
+    assert!(load(schema(), &json.replace("ecs-rs world", "something else")).contains("not a saved world"));
+}
@@ -0,0 +1,69 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{DataHelper, EcsError, Process, System, World};
+
+components! {
+    struct NoComponents;
+}
+
+#[derive(Default)]
+pub struct Log(Vec<&'static str>);
+impl ecs::ServiceManager for Log {}
+
+pub struct Record(&'static str);
+impl System for Record { type Components = NoComponents; type Services = Log; }
+impl Process for Record
+{
+    fn process(&mut self, data: &mut DataHelper<NoComponents, Log>)
+    {
+        data.services.0.push(self.0);
+    }
+}
+
+systems! {
+    struct OrderedSystems<NoComponents, Log> {
+        active: {
+            render: Record = Record("render") => [after(simulation)],
+            physics: Record = Record("physics") => [label(simulation), after(input)],
+            ai: Record = Record("ai") => [label(simulation)],
+            input: Record = Record("input"),
+            audio: Record = Record("audio"),
+        },
+        passive: {}
+    }
+}
+
+systems! {
+    struct CyclicSystems<NoComponents, Log> {
+        active: {
+            first: Record = Record("first") => [after(second)],
+            second: Record = Record("second") => [after(first)],
+        },
+        passive: {}
+    }
+}
+
+systems! {
+    struct UnknownLabelSystems<NoComponents, Log> {
+        active: {
+            first: Record = Record("first") => [before(nothing)],
+        },
+        passive: {}
+    }
+}
+
+#[test]
+fn test_constrained_order()
+{
+    let mut world = World::<OrderedSystems>::new();
+    world.update();
+    assert_eq!(vec!["ai", "input", "physics", "render", "audio"], world.services.0);
+}
+
+#[test]
+fn test_invalid_constraints()
+{
+    assert_eq!(EcsError::ScheduleCycle("first"), World::<CyclicSystems>::try_new().err().unwrap());
+    assert_eq!(EcsError::UnknownLabel("nothing"), World::<UnknownLabelSystems>::try_new().err().unwrap());
+}
@@ -0,0 +1,203 @@
+#![cfg(feature="serde")]
+
+#[macro_use]
+extern crate ecs;
+extern crate bincode;
+extern crate ron;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use ecs::{BuildData, Entity, Join, World};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Position
+{
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Frozen;
+
+components! {
+    #[derive(Serialize, Deserialize)]
+    struct Saved {
+        #[hot] position: Position,
+        #[cold] name: String,
+        #[dense] health: i32,
+        #[btree] target: Entity,
+        #[tag] frozen: Frozen,
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Score(u32);
+impl ecs::ServiceManager for Score {}
+
+systems! {
+    struct NoSystems<Saved, Score>;
+}
+
+fn build() -> (World<NoSystems>, Entity, Entity)
+{
+    let mut world = World::<NoSystems>::new();
+    world.services.0 = 42;
+    let gone = world.create_entity(());
+    let ship = world.create_entity(|e: BuildData<Saved>, c: &mut Saved| {
+        c.position.add(&e, Position { x: 1.0, y: 2.5 });
+        c.name.add(&e, "ship".to_string());
+        c.health.add(&e, 10);
+        c.frozen.add(&e, Frozen);
+    });
+    let turret = world.create_entity(|e: BuildData<Saved>, c: &mut Saved| {
+        c.target.add(&e, ship);
+        c.health.add(&e, 3);
+    });
+    world.set_parent(turret, Some(ship)).unwrap();
+    world.remove_entity(gone);
+    world.flush_queue();
+    (world, ship, turret)
+}
+
+fn check(world: &mut World<NoSystems>, ship: Entity, turret: Entity)
+{
+    assert_eq!(42, world.services.0);
+    assert_eq!(vec![ship, turret], world.entities().map(|e| **e).collect::<Vec<_>>());
+    assert_eq!(Some(ship), world.parent(&turret));
+    world.with_entity_data(&ship, |e, c| {
+        assert_eq!(Position { x: 1.0, y: 2.5 }, c.position[e]);
+        assert_eq!("ship", c.name[e]);
+        assert_eq!(10, c.health[e]);
+        assert!(c.frozen.has(&e));
+    }).unwrap();
+    world.with_entity_data(&turret, |e, c| {
+        assert_eq!(ship, c.target[e]);
+        assert!(!c.position.has(&e));
+    }).unwrap();
+
+    // The removed entity's index is reused with a new generation.
+    let next = world.create_entity(());
+    assert!(next.index() == 0 && next.generation() == 2);
+}
+
+#[test]
+fn test_json()
+{
+    let (world, ship, turret) = build();
+    let json = serde_json::to_string(&world).unwrap();
+    let mut loaded: World<NoSystems> = serde_json::from_str(&json).unwrap();
+    check(&mut loaded, ship, turret);
+}
+
+#[test]
+fn test_bincode()
+{
+    let (world, ship, turret) = build();
+    let bytes = bincode::serialize(&world).unwrap();
+    let mut loaded: World<NoSystems> = bincode::deserialize(&bytes).unwrap();
+    check(&mut loaded, ship, turret);
+}
+
+#[test]
+fn test_ron()
+{
+    let (world, ship, turret) = build();
+    let text = ron::to_string(&world).unwrap();
+    let mut loaded: World<NoSystems> = ron::from_str(&text).unwrap();
+    check(&mut loaded, ship, turret);
+}
+
+#[test]
+fn test_pending_queue()
+{
+    let (mut world, ship, turret) = build();
+    let spare = world.create_entity(|e: BuildData<Saved>, c: &mut Saved| { c.health.add(&e, 1); });
+    world.remove_entity(ship);
+
+    // Saving doesn't flush, and loading keeps the queue for the next flush.
+    let json = serde_json::to_string(&world).unwrap();
+    let mut loaded: World<NoSystems> = serde_json::from_str(&json).unwrap();
+    assert!(loaded.try_with_entity_data(&turret, |_, _| ()).is_err());
+    assert_eq!(Some(1), loaded.with_entity_data(&spare, |e, c| c.health[e]));
+    assert_eq!(3, loaded.entities().count());
+
+    loaded.flush_queue();
+    world.flush_queue();
+    assert_eq!(vec![spare], loaded.entities().map(|e| **e).collect::<Vec<_>>());
+    assert_eq!(serde_json::to_string(&world).unwrap(), serde_json::to_string(&loaded).unwrap());
+}
+
+#[test]
+fn test_changes_after_load()
+{
+    let (world, _, _) = build();
+    let json = serde_json::to_string(&world).unwrap();
+    let mut loaded: World<NoSystems> = serde_json::from_str(&json).unwrap();
+    let tick = loaded.position.changes().tick();
+    assert_eq!(0, loaded.position.changes().changed_since(0).count());
+
+    // Loaded components are tracked even when only reached through a join.
+    for (position,) in (&mut loaded.data.components.position,).join()
+    {
+        position.x += 1.0;
+    }
+    assert_eq!(1, loaded.position.changes().changed_since(tick).count());
+}
+
+#[test]
+fn test_slice_changes_after_load()
+{
+    let (world, _, _) = build();
+    let json = serde_json::to_string(&world).unwrap();
+    let mut loaded: World<NoSystems> = serde_json::from_str(&json).unwrap();
+    let tick = loaded.health.changes().tick();
+    for health in loaded.data.components.health.as_mut_slice()
+    {
+        *health -= 1;
+    }
+    assert_eq!(2, loaded.health.changes().changed_since(tick).count());
+}
+
+#[test]
+fn test_invalid_entities()
+{
+    let (world, ship, _) = build();
+    let json = serde_json::to_string(&world).unwrap();
+    assert!(serde_json::from_str::<World<NoSystems>>(&json).is_ok());
+
+    // A live entity with a stale generation, or one whose index is also free, is rejected
+    // instead of breaking the pool later.
+    let stale = format!("[{},{}]", ship.index(), ship.generation());
+    let broken = json.replacen(&stale, &format!("[{},{}]", ship.index(), ship.generation() + 1), 1);
+    assert!(serde_json::from_str::<World<NoSystems>>(&broken).is_err());
+    let broken = json.replacen("[[0],", &format!("[[0,{}],", ship.index()), 1);
+    assert!(serde_json::from_str::<World<NoSystems>>(&broken).is_err());
+
+    // Every index must be either live or free, and generation 0 is never used.
+    let pool = "[[0],3,[2,1,1]]";
+    assert!(json.contains(pool));
+    let leaked = json.replacen(pool, "[[],3,[2,1,1]]", 1);
+    assert!(serde_json::from_str::<World<NoSystems>>(&leaked).is_err());
+    let nil = json.replacen(pool, "[[0],3,[0,1,1]]", 1);
+    assert!(serde_json::from_str::<World<NoSystems>>(&nil).is_err());
+}
+
+#[test]
+fn test_commands_not_saved()
+{
+    let (mut world, ship, turret) = build();
+    world.data.commands().remove_entity(turret);
+    world.data.commands().insert(ship, |c| &mut c.health, 99);
+
+    // The commands are dropped, so nothing changes when the loaded world is flushed.
+    let json = serde_json::to_string(&world).unwrap();
+    let mut loaded: World<NoSystems> = serde_json::from_str(&json).unwrap();
+    assert!(loaded.data.commands().is_empty());
+    loaded.flush_queue();
+    assert!(loaded.is_valid(&turret));
+    assert_eq!(Some(10), loaded.with_entity_data(&ship, |e, c| c.health[e]));
+
+    world.flush_queue();
+    assert!(!world.is_valid(&turret));
+}
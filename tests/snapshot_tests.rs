@@ -0,0 +1,162 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{DataHelper, Entity, EntityIter, ModifyData, System, World};
+use ecs::system::{EntityProcess, EntitySystem, ReactiveBatch, ReactiveProcess, ReactiveSystem};
+
+components! {
+    #[builder(Parts)]
+    #[derive(Clone)]
+    struct Body {
+        #[hot] position: i32,
+        #[cold] velocity: i32,
+        #[tag] frozen: (),
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Stats
+{
+    frames: u32,
+    moved: usize,
+    modified: Vec<Entity>,
+}
+impl ecs::ServiceManager for Stats {}
+
+/// Moves everything that isn't frozen.
+pub struct Move;
+impl System for Move { type Components = Body; type Services = Stats; }
+impl EntityProcess for Move
+{
+    fn process(&mut self, entities: EntityIter<Body>, data: &mut DataHelper<Body, Stats>)
+    {
+        data.services.frames += 1;
+        data.services.moved = 0;
+        for e in entities
+        {
+            data.position[e] += data.velocity[e];
+            data.services.moved += 1;
+        }
+    }
+}
+
+/// Records the entities whose position changed.
+pub struct Watch;
+impl System for Watch { type Components = Body; type Services = Stats; }
+impl ReactiveProcess for Watch
+{
+    fn process(&mut self, batch: ReactiveBatch<Body>, data: &mut DataHelper<Body, Stats>)
+    {
+        data.services.modified = batch.modified().map(|e| **e).collect();
+    }
+}
+
+systems! {
+    struct Physics<Body, Stats> {
+        active: {
+            movement: EntitySystem<Move> = EntitySystem::new(Move, aspect!(<Body> all: [position, velocity] none: [frozen])),
+            watch: ReactiveSystem<Watch> = ReactiveSystem::new(Watch, aspect!(<Body> all: [position]))
+                .watch(|c| c.position.changes()),
+        },
+        passive: {}
+    }
+}
+
+#[test]
+fn test_rollback()
+{
+    let mut world = World::<Physics>::new();
+    let ball = world.create_entity(Parts { position: Some(0), velocity: Some(1), ..Default::default() });
+    world.update();
+    let snapshot = world.snapshot();
+
+    // Diverge: spawn an entity, freeze the ball and run a few frames.
+    let extra = world.create_entity(Parts { position: Some(100), velocity: Some(10), ..Default::default() });
+    world.modify_entity(ball, |e: ModifyData<Body>, c: &mut Body| { c.frozen.insert(&e, ()); });
+    for _ in 0..3
+    {
+        world.update();
+    }
+    assert_eq!(Some(1), world.with_entity_data(&ball, |e, c| c.position[e]));
+    assert_eq!(4, world.services.frames);
+
+    world.rollback(&snapshot);
+    assert!(!world.is_valid(&extra));
+    assert_eq!(1, world.entities().count());
+    assert_eq!(1, world.services.frames);
+    world.update();
+    assert_eq!(Some(2), world.with_entity_data(&ball, |e, c| c.position[e]));
+    assert_eq!(1, world.services.moved);
+
+    // The same snapshot can be restored again, and the removed entity's index is reused.
+    world.rollback(&snapshot);
+    let again = world.create_entity(Parts { position: Some(0), velocity: Some(0), ..Default::default() });
+    assert_eq!(extra, again);
+}
+
+#[test]
+fn test_rollback_pending()
+{
+    let mut world = World::<Physics>::new();
+    world.create_entity(Parts { position: Some(0), velocity: Some(1), ..Default::default() });
+    let snapshot = world.snapshot();
+    world.flush_queue();
+    world.update();
+
+    // The entity hadn't been flushed when the snapshot was taken, so the next flush adds it.
+    world.rollback(&snapshot);
+    assert_eq!(0, world.services.frames);
+    world.update();
+    assert_eq!(1, world.services.moved);
+}
+
+#[test]
+fn test_rollback_changes()
+{
+    let mut world = World::<Physics>::new();
+    let ball = world.create_entity(Parts { position: Some(0), velocity: Some(1), ..Default::default() });
+    world.update();
+    let snapshot = world.snapshot();
+    for _ in 0..3
+    {
+        world.update();
+    }
+    assert_eq!(vec![ball], world.services.modified);
+
+    // Changes made after a rollback come after everything seen before it.
+    let seen = world.position.changes().tick();
+    world.rollback(&snapshot);
+    world.with_entity_data(&ball, |e, c| c.position[e] = 10);
+    assert_eq!(1, world.position.changes().changed_since(seen).count());
+
+    // The ball comes back as a new entry, then its changes are reported as usual.
+    world.update();
+    assert!(world.services.modified.is_empty());
+    world.update();
+    assert_eq!(vec![ball], world.services.modified);
+}
+
+#[test]
+fn test_rollback_reverts()
+{
+    let mut world = World::<Physics>::new();
+    let ball = world.create_entity(Parts { position: Some(0), velocity: Some(1), ..Default::default() });
+    let wall = world.create_entity(Parts { position: Some(5), velocity: Some(0), frozen: Some(()) });
+    world.flush_queue();
+    let snapshot = world.snapshot();
+
+    // Move the ball, take the frozen wall's velocity and freeze the ball.
+    world.update();
+    world.modify_entity(wall, |e: ModifyData<Body>, c: &mut Body| { c.velocity.remove(&e); });
+    world.modify_entity(ball, |e: ModifyData<Body>, c: &mut Body| { c.frozen.insert(&e, ()); });
+    world.flush_queue();
+    let seen = (world.position.changes().tick(), world.velocity.changes().tick(), world.frozen.changes().tick());
+
+    world.rollback(&snapshot);
+    let moved: Vec<_> = world.position.changes().changed_since(seen.0).map(|e| **e).collect();
+    assert_eq!(vec![ball], moved);
+    let restored: Vec<_> = world.velocity.changes().added_since(seen.1).map(|e| **e).collect();
+    assert_eq!(vec![wall], restored);
+    let thawed: Vec<_> = world.frozen.changes().removed_since(seen.2).collect();
+    assert_eq!(vec![ball], thawed);
+}
@@ -0,0 +1,119 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{Join, ModifyData, Storage, World};
+
+/// A storage defined outside the crate.
+pub struct Slots<T>(Vec<Option<T>>);
+
+impl<T: 'static> Storage<T> for Slots<T>
+{
+    fn new() -> Slots<T> { Slots(Vec::new()) }
+    fn len(&self) -> usize { self.0.iter().filter(|s| s.is_some()).count() }
+    fn get(&self, index: usize) -> Option<&T> { self.0.get(index).and_then(|s| s.as_ref()) }
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> { self.0.get_mut(index).and_then(|s| s.as_mut()) }
+    fn insert(&mut self, index: usize, component: T) -> Option<T>
+    {
+        while self.0.len() <= index { self.0.push(None); }
+        self.0[index].replace(component)
+    }
+    fn remove(&mut self, index: usize) -> Option<T> { self.0.get_mut(index).and_then(|s| s.take()) }
+    fn iter<'a>(&'a self) -> Box<Iterator<Item=(usize, &'a T)> + 'a>
+    {
+        Box::new(self.0.iter().enumerate().filter_map(|(i, s)| s.as_ref().map(|c| (i, c))))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frozen;
+
+components! {
+    #[builder(Parts)]
+    struct Stored {
+        #[hot] hot: i32,
+        #[cold] cold: i32,
+        #[dense] dense: i32,
+        #[btree] btree: i32,
+        #[tag] frozen: Frozen,
+        #[storage(Slots)] slot: i32,
+    }
+}
+
+systems! {
+    struct NoSystems<Stored, ()>;
+}
+
+fn parts(value: i32) -> Parts
+{
+    Parts
+    {
+        hot: Some(value),
+        cold: Some(value),
+        dense: Some(value),
+        btree: Some(value),
+        frozen: if value % 2 == 0 { Some(Frozen) } else { None },
+        slot: Some(value),
+    }
+}
+
+#[test]
+fn test_storages()
+{
+    let mut world = World::<NoSystems>::new();
+    let entities: Vec<_> = (0..6).map(|i| world.create_entity(parts(i))).collect();
+
+    // Remove from the middle so the dense storage has to move its last component.
+    world.modify_entity(entities[1], |e: ModifyData<Stored>, c: &mut Stored| {
+        assert_eq!(Some(1), c.dense.remove(&e));
+        assert_eq!(Some(1), c.slot.remove(&e));
+    });
+    world.remove_entity(entities[2]);
+    world.flush_queue();
+
+    for entity in world.entities()
+    {
+        let value = world.hot[entity];
+        assert_eq!(value, world.cold[entity]);
+        assert_eq!(value, world.btree[entity]);
+        assert_eq!(value % 2 == 0, world.frozen.has(&entity));
+        if value != 1
+        {
+            assert_eq!(value, world.dense[entity]);
+            assert_eq!(value, world.slot[entity]);
+        }
+    }
+
+    // The dense list stays packed after removals.
+    let mut packed = world.dense.as_slice().to_vec();
+    packed.sort();
+    assert_eq!(vec![0, 3, 4, 5], packed);
+    assert_eq!(4, world.dense.storage().indices().len());
+
+    let c = &world.data.components;
+    let mut frozen: Vec<_> = (&c.frozen, &c.dense, &c.btree).join().map(|(_, &d, &b)| (d, b)).collect();
+    frozen.sort();
+    assert_eq!(vec![(0, 0), (4, 4)], frozen);
+    assert_eq!(4, (&c.slot, &c.cold).join().count());
+}
+
+#[test]
+fn test_dense_slices()
+{
+    let mut world = World::<NoSystems>::new();
+    let entities: Vec<_> = (0..4).map(|i| world.create_entity(parts(i))).collect();
+    world.flush_queue();
+
+    // Removing swaps the last component into the hole and updates its index.
+    world.modify_entity(entities[0], |e: ModifyData<Stored>, c: &mut Stored| { c.dense.remove(&e); });
+    assert_eq!(&[3, 1, 2], world.dense.as_slice());
+    assert_eq!(&[entities[3].index(), entities[1].index(), entities[2].index()], world.dense.storage().indices());
+
+    // Writing through the slice counts as changing every component.
+    let tick = world.dense.changes().tick();
+    for value in world.data.components.dense.as_mut_slice()
+    {
+        *value *= 10;
+    }
+    assert_eq!(&[30, 10, 20], world.dense.as_slice());
+    assert_eq!(3, world.dense.changes().changed_since(tick).count());
+}
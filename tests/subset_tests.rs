@@ -0,0 +1,98 @@
+#![cfg(feature="serde")]
+
+#[macro_use]
+extern crate ecs;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use ecs::{Entity, World};
+
+components! {
+    #[builder(Parts)]
+    #[derive(Serialize, Deserialize)]
+    struct Game {
+        #[hot] name: String,
+        #[cold] item: (),
+    }
+}
+
+systems! {
+    struct NoSystems<Game, ()>;
+}
+
+fn names(world: &World<NoSystems>) -> Vec<String>
+{
+    let mut names: Vec<String> = world.entities().map(|e| world.data.components.name[e].clone()).collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_save_matching()
+{
+    let mut world = World::<NoSystems>::new();
+    let player = world.create_entity(Parts { name: Some("player".to_string()), ..Default::default() });
+    let sword = world.create_entity(Parts { name: Some("sword".to_string()), item: Some(()) });
+    let gem = world.create_entity(Parts { name: Some("gem".to_string()), ..Default::default() });
+    world.create_entity(Parts { name: Some("shield".to_string()), item: Some(()) });
+    world.create_entity(Parts { name: Some("tree".to_string()), ..Default::default() });
+    world.set_parent(sword, Some(player)).unwrap();
+    world.set_parent(gem, Some(sword)).unwrap();
+    world.flush_queue();
+
+    let items: Vec<Entity> = world.entities().filter(aspect!(<Game> all: [item]), &world.data.components).map(|e| **e).collect();
+    let json = {
+        let mut json = Vec::new();
+        world.save_entities(items, &mut serde_json::Serializer::new(&mut json)).unwrap();
+        String::from_utf8(json).unwrap()
+    };
+
+    let mut other = World::<NoSystems>::new();
+    other.create_entity(Parts { name: Some("chest".to_string()), ..Default::default() });
+    let map = other.merge_saved(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+    other.flush_queue();
+
+    // The gem comes along as the sword's child, but the player doesn't.
+    assert_eq!(vec!["chest", "gem", "shield", "sword"], names(&other));
+    assert_eq!(3, map.len());
+    assert_eq!(None, other.parent(&map.map(sword)));
+    assert_eq!(Some(map.map(sword)), other.parent(&map.map(gem)));
+}
+
+#[test]
+fn test_save_pending()
+{
+    let mut world = World::<NoSystems>::new();
+    let tree = world.create_entity(Parts { name: Some("tree".to_string()), ..Default::default() });
+    world.flush_queue();
+    let stump = world.create_entity(Parts { name: Some("stump".to_string()), ..Default::default() });
+    world.create_entity(Parts { name: Some("log".to_string()), ..Default::default() });
+    world.remove_entity(tree);
+
+    let mut json = Vec::new();
+    world.save_entities(vec![tree, stump], &mut serde_json::Serializer::new(&mut json)).unwrap();
+    let mut loaded: World<NoSystems> = serde_json::from_slice(&json).unwrap();
+    loaded.flush_queue();
+    assert_eq!(vec!["stump"], names(&loaded));
+}
+
+#[test]
+fn test_load_subset()
+{
+    let mut world = World::<NoSystems>::new();
+    world.create_entity(Parts { name: Some("tree".to_string()), ..Default::default() });
+    let stump = world.create_entity(Parts { name: Some("stump".to_string()), ..Default::default() });
+    world.create_entity(Parts { name: Some("log".to_string()), ..Default::default() });
+    world.flush_queue();
+
+    let mut json = Vec::new();
+    world.save_entities(vec![stump], &mut serde_json::Serializer::new(&mut json)).unwrap();
+    let mut loaded: World<NoSystems> = serde_json::from_slice(&json).unwrap();
+    assert_eq!(vec!["stump"], names(&loaded));
+
+    // The indices of the entities that were left out are free again.
+    let mut indices: Vec<usize> = (0..2).map(|_| loaded.create_entity(()).index()).collect();
+    indices.sort();
+    assert_eq!(vec![0, 2], indices);
+}